@@ -0,0 +1 @@
+vim.cmd[[autocmd BufRead,BufNewFile *.cypher set filetype=cypher]]
@@ -0,0 +1 @@
+require 'dbeer.core'.init_msg()
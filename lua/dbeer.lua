@@ -22,7 +22,12 @@ M.SETTINGS = {
         border_style = 1,
         header_style_link = "Type",
         buffer_height = 20,
-        override = false
+        override = false,
+        max_rows = 1000,
+        max_cell_width = 100,
+        multiline = "first",
+        thousands_separator = "",
+        decimal_precision = nil
     },
     db = {
         default = 1,
@@ -107,6 +112,26 @@ function M.setup(opts)
             M.SETTINGS.output.buffer_height = (type(op.buffer_height) == "number" and op.buffer_height > 10 and op.buffer_height < 90 and op.buffer_height) or
                 M.SETTINGS.output.buffer_height
         end
+        if op.max_rows then
+            M.SETTINGS.output.max_rows = (type(op.max_rows) == "number" and op.max_rows >= 0 and op.max_rows) or
+                M.SETTINGS.output.max_rows
+        end
+        if op.max_cell_width then
+            M.SETTINGS.output.max_cell_width = (type(op.max_cell_width) == "number" and op.max_cell_width >= 0 and op.max_cell_width) or
+                M.SETTINGS.output.max_cell_width
+        end
+        if op.multiline then
+            M.SETTINGS.output.multiline = (vim.tbl_contains({ "first", "join", "escape" }, op.multiline) and op.multiline) or
+                M.SETTINGS.output.multiline
+        end
+        if op.thousands_separator then
+            M.SETTINGS.output.thousands_separator = (type(op.thousands_separator) == "string" and op.thousands_separator) or
+                M.SETTINGS.output.thousands_separator
+        end
+        if op.decimal_precision then
+            M.SETTINGS.output.decimal_precision = (type(op.decimal_precision) == "number" and op.decimal_precision >= 0 and op.decimal_precision) or
+                M.SETTINGS.output.decimal_precision
+        end
         if op.override ~= nil then
             M.SETTINGS.output.override = (type(op.override) == "boolean" and op.override) or
                 M.SETTINGS.output.override
@@ -141,7 +166,9 @@ function M.setup(opts)
             M.SETTINGS.internal.log_debug
     end
 
-    util.logger:debug("Configuration: " .. vim.inspect(M.SETTINGS))
+    util.logger:debug("Configuration: " .. vim.inspect(M.SETTINGS, {
+        process = function(item, path) return path[#path] == "password" and "********" or item end
+    }))
 
     local function map_or_unmap(connection, is_mongo)
         if connection.name and connection.dbname and connection.engine and require 'dbeer.engines'.db[connection.engine] and is_mongo then
@@ -10,7 +10,7 @@ function M.init_msg()
 
     if db.connections then
         local connection = db.connections[require 'dbeer'.default_db]
-        if connection.name and connection.dbname and connection.engine and require 'dbeer.engines'.db[connection.engine] then
+        if connection.name and (connection.dbname or connection.profile) and connection.engine and require 'dbeer.engines'.db[connection.engine] then
             logger:info(string.format("Database set to [%s]", connection.name))
         end
     else
@@ -22,12 +22,18 @@ function M.get_connection_string()
     local db = setup.db
     if db.connections then
         local connection = db.connections[require 'dbeer'.default_db]
-        local conn_str = engines.db[connection.engine].get_connection_string(connection)
-        util.logger:debug(conn_str)
-        return conn_str
+        -- The binary reads the connection of a profile, so no password is in its arguments
+        if connection.profile then
+            return ""
+        end
+        return engines.db[connection.engine].get_connection_string(connection)
     end
 end
 
+function M.profile_args(conn)
+    return conn.profile and (" -profile " .. vim.fn.shellescape(conn.profile)) or ""
+end
+
 local function get_buffer_content()
     local mode = vim.api.nvim_get_mode().mode
 
@@ -56,7 +62,7 @@ local function get_buffer_content()
     end
 end
 
-function M.run()
+function M.run(extra_args)
     local file_with_extension = vim.fn.expand("%:p")
     if setup.output.override then
         M.close()
@@ -70,16 +76,32 @@ function M.run()
     end
 
     local dest_folder = setup.output.dest_folder
-    local format_query = ((conn.engine == "mongo" or conn.engine == "redis") and "'%s'") or '\"%s\"'
+    local number_args = ""
+    if setup.output.thousands_separator ~= "" then
+        number_args = number_args .. " -thousands-separator " .. vim.fn.shellescape(setup.output.thousands_separator)
+    end
+    if setup.output.decimal_precision then
+        number_args = number_args .. " -decimal-precision " .. setup.output.decimal_precision
+    end
+    if conn.timeout_ms then
+        number_args = number_args .. " -timeout-ms " .. conn.timeout_ms
+    end
+    if conn.name then
+        number_args = number_args .. " -conn-name " .. vim.fn.shellescape(conn.name)
+    end
+    number_args = number_args .. M.profile_args(conn)
+    local conn_str = M.get_connection_string()
     local script = string.format(
-        "%s -engine %s -conn-str \"%s\" -queries " ..
-        format_query ..
-        " -dest-folder %s -border-style %d -header-style-link %s -dbeer-log-file %s -dbname %s -log-debug %s",
-        engines.db[conn.engine].executor, conn.engine, M.get_connection_string(), queries, dest_folder,
+        "%s -engine %s -conn-str \"%s\" -queries %s" ..
+        " -dest-folder %s -border-style %d -header-style-link %s -dbeer-log-file %s -dbname \"%s\" -log-debug %s -transaction %s -max-rows %d -max-cell-width %d -multiline %s",
+        engines.db[conn.engine].executor, conn.engine, conn_str, vim.fn.shellescape(queries), dest_folder,
         setup.output.border_style,
-        setup.output.header_style_link, util.dbeer_log_file, conn.dbname, setup.internal.log_debug)
+        setup.output.header_style_link, util.dbeer_log_file, conn.dbname or "", setup.internal.log_debug,
+        conn.transaction == true, setup.output.max_rows, setup.output.max_cell_width,
+        setup.output.multiline) .. number_args .. (extra_args or "")
 
-    util.logger:debug(script)
+    -- The connection string carries the password
+    util.logger:debug(conn_str ~= "" and script:gsub(vim.pesc(conn_str), "********") or script)
     local result = {}
     local elapsed_time = 0
     local spinner = spinetta:new {
@@ -100,6 +122,11 @@ function M.run()
                     vim.cmd("setlocal noma")
                     util.logger:info(string.format("  Query executed correctly [%.2f secs]", elapsed_time))
                     vim.cmd(result[1])
+                    -- Exports send the footer of the page after the file
+                    if result[3] then
+                        util.logger:info(result[3])
+                    end
+                    util.highlight_headers(0, result[2])
                 else
                     util.logger:info(result[1])
                 end
@@ -138,6 +165,91 @@ function M.run()
     vim.cmd(vim.fn.bufwinnr(file_with_extension) .. " wincmd w")
 end
 
+function M.explain(analyze)
+    M.run(string.format(" -option 4 -analyze %s", analyze == true))
+end
+
+function M.run_with_params(params)
+    M.run(" -params " .. vim.fn.shellescape(params))
+end
+
+function M.export(format)
+    M.run(" -output-format " .. vim.fn.shellescape(format) .. " -max-rows 0")
+end
+
+function M.insert(target_table, batch)
+    local args = " -output-format insert -max-rows 0"
+    if target_table and target_table ~= "" then
+        args = args .. " -insert-table " .. vim.fn.shellescape(target_table)
+    end
+    if tonumber(batch) then
+        args = args .. " -insert-batch " .. tonumber(batch)
+    end
+    M.run(args)
+end
+
+function M.page(number)
+    M.run(" -page " .. (tonumber(number) or 1))
+end
+
+-- Past executions, the latest first. Dates (YYYY-MM-DD) filter the days,
+-- one date is that day and two are a range. The rest is searched in the queries
+function M.history(args)
+    local dates, words = {}, {}
+    for _, arg in ipairs(args) do
+        if arg:match("^%d%d%d%d%-%d%d%-%d%d$") then
+            table.insert(dates, arg)
+        else
+            table.insert(words, arg)
+        end
+    end
+
+    local history_args = " -option 5 -search " .. vim.fn.shellescape(table.concat(words, " "))
+    if dates[1] then
+        history_args = history_args .. " -since " .. dates[1] .. " -until " .. (dates[2] or dates[1])
+    end
+    M.run(history_args)
+end
+
+-- Full value of the cell under the cursor in a results buffer.
+-- Truncated values are read from the .cells file written next to the .dbeer file
+function M.show_cell()
+    local file = vim.fn.expand("%:p")
+    local position = util.cursor_cell(file)
+    if not position then
+        util.logger:info("Place the cursor on a cell of a result table")
+        return
+    end
+
+    -- The display range of the layout, a border character can be part of the value
+    local chars = {}
+    local col = 0
+    local line = vim.api.nvim_get_current_line()
+    for i = 0, vim.fn.strchars(line) - 1 do
+        local char = vim.fn.strcharpart(line, i, 1)
+        if col >= position.start and col < position["end"] then
+            table.insert(chars, char)
+        end
+        col = col + vim.fn.strdisplaywidth(char)
+    end
+    local value = vim.trim(table.concat(chars))
+    local cells_file = file .. ".cells"
+    if vim.fn.filereadable(cells_file) == 1 then
+        local line_nr = vim.fn.line(".")
+        for json in io.lines(cells_file) do
+            local cell = vim.json.decode(json)
+            if cell.line == line_nr and cell.column == position.column then
+                value = cell.value
+                break
+            end
+        end
+    end
+
+    vim.cmd("new")
+    vim.api.nvim_buf_set_lines(0, 0, -1, false, vim.split(value, "\r?\n"))
+    vim.cmd("setlocal buftype=nofile bufhidden=wipe noswapfile noma")
+end
+
 function M.build()
     if vim.fn.executable("cargo") == 0 then
         util.logger:warn("Cargo (Rust) is required. Install it to use this plugin and then execute manually :dbeerBuild")
@@ -163,7 +275,7 @@ end
 function M.close()
     for _, nr in ipairs(vim.api.nvim_list_bufs()) do
         local buf_name = vim.api.nvim_buf_get_name(nr)
-        if vim.api.nvim_buf_is_loaded(nr) and (buf_name:find(".dbeer$") or buf_name:find(".dbeer.json$")) then
+        if vim.api.nvim_buf_is_loaded(nr) and (buf_name:find(".dbeer$") or buf_name:find("%.dbeer%.%a+$")) then
             vim.cmd("bd! " .. buf_name)
         end
     end
@@ -128,7 +128,7 @@ function M.expand()
             table.insert(lines, line_nr + 3, "       NAME     󰁕 " .. connection.name)
             table.insert(lines, line_nr + 4, "       HOST     󰁕 " .. (connection.host or db_const_data.default_host))
             table.insert(lines, line_nr + 5, "       PORT     󰁕 " .. (connection.port or db_const_data.default_port))
-            table.insert(lines, line_nr + 6, "       DB NAME  󰁕 " .. connection.dbname)
+            table.insert(lines, line_nr + 6, "       DB NAME  󰁕 " .. (connection.dbname or ("profile " .. connection.profile)))
             table.insert(lines, line_nr + 7,
                 "       USER     󰁕 " ..
                 (((connection.user and setup.view.show_user and connection.user) or connection.user and "********") or "-"))
@@ -5,6 +5,7 @@ local default_posgres_port = "5432"
 local default_mongo_port = "27017"
 local default_redis_port = "6379"
 local default_mysql_port = "3306"
+local default_neo4j_port = "7687"
 local exe = util.dbeer_root_path .. "bin/dbeer"
 
 local function odbc(connection)
@@ -76,6 +77,20 @@ return {
                 )
             end
         },
+        neo4j = {
+            title = "Neo4j",
+            default_port = default_neo4j_port,
+            default_host = host,
+            executor = exe,
+            get_connection_string = function(connection)
+                return string.format("bolt://%s%s%s:%s",
+                    connection.user and connection.user or "",
+                    connection.user and ((connection.password and ":" .. connection.password or "") .. "@") or "",
+                    connection.host or host,
+                    connection.port or default_neo4j_port
+                )
+            end
+        },
         mssql = {
             title = "MS-SQL",
             default_port = "-",
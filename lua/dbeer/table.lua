@@ -45,10 +45,11 @@ local function show_table_info(table_selected)
     end
 
     local result = vim.fn.system(string.format(
-        "%s -option 3 -engine %s -conn-str \"%s\" -queries %s -border-style %d -header-style-link %s -dbeer-log-file %s -dbname %s -log-debug %s",
+        "%s -option 3 -engine %s -conn-str \"%s\" -queries %s -border-style %d -header-style-link %s -dbeer-log-file %s -dbname \"%s\" -log-debug %s",
         engines.db[conn.engine].executor, conn.engine, core.get_connection_string(), table_selected,
         setup.output.border_style,
-        setup.output.header_style_link, util.dbeer_log_file, conn.dbname, setup.internal.log_debug))
+        setup.output.header_style_link, util.dbeer_log_file, conn.dbname or "", setup.internal.log_debug) ..
+        core.profile_args(conn))
 
     util.logger:debug("results backend:" .. result)
 
@@ -77,6 +78,7 @@ local function show_table_info(table_selected)
                     vim.cmd [[ setlocal nowrap ]]
                     vim.cmd [[ setl noma ]]
                     vim.cmd(line_1)
+                    util.highlight_headers(0, dbeer_file)
                 end
             }
 
@@ -98,7 +100,8 @@ local function get_tables()
     local result = vim.fn.system(string.format(
         "%s -option 2 -engine %s -conn-str \"%s\" -dbeer-log-file %s -dbname \"%s\" -log-debug %s",
         engines.db[conn.engine].executor, conn.engine,
-        core.get_connection_string(), util.dbeer_log_file, conn.dbname, setup.internal.log_debug))
+        core.get_connection_string(), util.dbeer_log_file, conn.dbname or "", setup.internal.log_debug) ..
+        core.profile_args(conn))
     if result ~= "" then
         util.logger:debug(result)
 
@@ -56,6 +56,61 @@ function M.count_UTF8_characters(str)
     return count
 end
 
+local layout_ns = vim.api.nvim_create_namespace("dbeer_layout")
+
+-- Index of the tables of a results file, written next to it by the backend
+function M.read_layout(file)
+    local layout_file = file .. ".layout"
+    if vim.fn.filereadable(layout_file) == 0 then
+        return nil
+    end
+    local ok, layout = pcall(vim.json.decode, table.concat(vim.fn.readfile(layout_file), "\n"))
+    return (ok and layout) or nil
+end
+
+-- Highlights the header names of every table in the buffer of a results file
+function M.highlight_headers(bufnr, file)
+    local layout = M.read_layout(file)
+    if not layout then
+        return
+    end
+    vim.api.nvim_buf_clear_namespace(bufnr, layout_ns, 0, -1)
+    for _, tbl in ipairs(layout.tables) do
+        for _, column in ipairs(tbl.columns) do
+            vim.api.nvim_buf_set_extmark(bufnr, layout_ns, tbl.header_line - 1, column.name_start,
+                { end_col = column.name_end, hl_group = "dbeerHeader" })
+        end
+    end
+end
+
+-- Row number, column and display range of the table cell under the cursor
+function M.cursor_cell(file)
+    local layout = M.read_layout(file)
+    if not layout then
+        return nil
+    end
+    local line = vim.fn.line(".")
+    local col = vim.fn.virtcol(".") - 1
+    for _, tbl in ipairs(layout.tables) do
+        local rows = tbl.rows
+        local offset = line - rows.first_line
+        if offset >= 0 and offset % rows.step == 0 and offset / rows.step < rows.count then
+            for _, column in ipairs(tbl.columns) do
+                if col >= column.start and col < column["end"] then
+                    return {
+                        row = rows.first_number + offset / rows.step,
+                        column = column.column,
+                        name = column.name,
+                        start = column.start,
+                        ["end"] = column["end"],
+                    }
+                end
+            end
+        end
+    end
+    return nil
+end
+
 function M.get_numeral_sprinner()
     local numbers = {}
 
@@ -19,3 +19,31 @@ end, {})
 vim.api.nvim_create_user_command('DBeerDB', function()
     require("dbeer.database").show()
 end, {})
+
+vim.api.nvim_create_user_command('DBeerExplain', function(opts)
+    require("dbeer.core").explain(opts.args == "analyze")
+end, { nargs = "?", range = true, complete = function() return { "analyze" } end })
+
+vim.api.nvim_create_user_command('DBeerParams', function(opts)
+    require("dbeer.core").run_with_params(opts.args)
+end, { nargs = 1, range = true })
+
+vim.api.nvim_create_user_command('DBeerExport', function(opts)
+    require("dbeer.core").export(opts.args)
+end, { nargs = 1, range = true, complete = function() return { "csv", "tsv", "json", "ndjson" } end })
+
+vim.api.nvim_create_user_command('DBeerInsert', function(opts)
+    require("dbeer.core").insert(opts.fargs[1], opts.fargs[2])
+end, { nargs = "*", range = true })
+
+vim.api.nvim_create_user_command('DBeerPage', function(opts)
+    require("dbeer.core").page(opts.args)
+end, { nargs = 1, range = true })
+
+vim.api.nvim_create_user_command('DBeerHistory', function(opts)
+    require("dbeer.core").history(opts.fargs)
+end, { nargs = "*" })
+
+vim.api.nvim_create_user_command('DBeerCell', function()
+    require("dbeer.core").show_cell()
+end, {})
@@ -13,7 +13,7 @@ pub struct Border {
     pub vertical_right: &'static str,
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub enum BorderStyle {
     #[default]
     Default,
@@ -0,0 +1,67 @@
+use std::io;
+use std::process;
+use std::sync::atomic::{AtomicI32, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::{dbeer_debug, dbeer_error};
+
+/// Cancels the statement running on the server. Called from another thread
+pub type Canceller = Box<dyn Fn() + Send>;
+
+static CANCELLER: LazyLock<Mutex<Option<Canceller>>> = LazyLock::new(|| Mutex::new(None));
+
+/// Last signal received, 0 when none
+static SIGNAL: AtomicI32 = AtomicI32::new(0);
+
+/// Time given to the running command to report the cancellation before exiting
+const GRACE: Duration = Duration::from_secs(3);
+const POLL: Duration = Duration::from_millis(50);
+
+/// Sets what cancels the statement which is about to run, `None` once it is done
+pub fn set_canceller(canceller: Option<Canceller>) {
+    if let Ok(mut current) = CANCELLER.lock() {
+        *current = canceller;
+    }
+}
+
+/// On SIGINT or SIGTERM the running statement is cancelled on the server. The command
+/// reports it and exits with `exit_if_signaled`, with 128 + the signal number as status.
+/// Without a running statement the process exits at once.
+/// The signal handler only stores the signal, a watcher thread does the rest
+pub fn cancel_on_signals() -> io::Result<()> {
+    for signal in [libc::SIGINT, libc::SIGTERM] {
+        // Storing an atomic is async-signal-safe
+        unsafe {
+            signal_hook_registry::register(signal, move || SIGNAL.store(signal, Ordering::SeqCst))
+        }?;
+    }
+
+    thread::spawn(|| {
+        let signal = loop {
+            match SIGNAL.load(Ordering::SeqCst) {
+                0 => thread::sleep(POLL),
+                signal => break signal,
+            }
+        };
+
+        let canceller = CANCELLER.lock().ok().and_then(|mut current| current.take());
+        if let Some(cancel) = canceller {
+            dbeer_debug!("Signal {signal} received. Cancelling the running statement");
+            cancel();
+            thread::sleep(GRACE);
+            dbeer_error!("[ERROR] The statement has not stopped after signal {signal}");
+        }
+        process::exit(128 + signal);
+    });
+    Ok(())
+}
+
+/// Called once the response is written
+pub fn exit_if_signaled() {
+    match SIGNAL.load(Ordering::SeqCst) {
+        0 => {}
+        signal => process::exit(128 + signal),
+    }
+}
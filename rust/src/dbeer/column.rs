@@ -0,0 +1,237 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use super::query::Truncation;
+
+/// Kind of the values of a column, given by the engine from the column type.
+/// It decides how the cells of the column are aligned and formatted
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ColumnKind {
+    #[default]
+    Text,
+    Integer,
+    Decimal,
+    Boolean,
+    /// Hex literals like `x'CAFE'`, `0xCAFE` or `\xcafe`, shortened in the cells
+    Binary,
+    /// `2025-06-03`
+    Date,
+    /// `2025-06-03 10:15:00`, without time zone
+    Timestamp,
+}
+
+impl ColumnKind {
+    /// Numeric columns are right aligned
+    pub fn is_numeric(&self) -> bool {
+        matches!(self, ColumnKind::Integer | ColumnKind::Decimal)
+    }
+}
+
+/// Formatting of numeric cells. Values which are not plain numbers
+/// (scientific notation, NaN, Infinity...) are shown as they come
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct NumberFormat {
+    /// Separator of the thousands of the integer part
+    pub thousands_separator: Option<char>,
+    /// Fixed number of decimals of decimal columns, rounded half away from zero
+    pub precision: Option<usize>,
+}
+
+impl NumberFormat {
+    pub fn apply(&self, kind: ColumnKind, value: &str) -> String {
+        static NUMBER: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"^([-+]?)(\d+)(?:\.(\d+))?$").unwrap());
+
+        let Some(captures) = NUMBER.captures(value.trim()) else {
+            return value.to_string();
+        };
+        let sign = &captures[1];
+        let mut integer = captures[2].to_string();
+        let mut fraction = captures.get(3).map_or("", |m| m.as_str()).to_string();
+
+        if kind == ColumnKind::Decimal
+            && let Some(precision) = self.precision
+        {
+            (integer, fraction) = Self::round(&integer, &fraction, precision);
+        }
+
+        if let Some(separator) = self.thousands_separator {
+            integer = Self::group(&integer, separator);
+        }
+
+        if fraction.is_empty() {
+            format!("{sign}{integer}")
+        } else {
+            format!("{sign}{integer}.{fraction}")
+        }
+    }
+
+    /// Works on the digits, so NUMERIC values keep their exact precision
+    fn round(integer: &str, fraction: &str, precision: usize) -> (String, String) {
+        if fraction.len() <= precision {
+            return (integer.to_string(), format!("{fraction:0<precision$}"));
+        }
+
+        let mut digits: Vec<u8> = format!("{integer}{}", &fraction[..precision]).into_bytes();
+        if fraction.as_bytes()[precision] >= b'5' {
+            let mut carry = true;
+            for digit in digits.iter_mut().rev() {
+                if *digit == b'9' {
+                    *digit = b'0';
+                } else {
+                    *digit += 1;
+                    carry = false;
+                    break;
+                }
+            }
+            if carry {
+                digits.insert(0, b'1');
+            }
+        }
+
+        let digits = String::from_utf8(digits).unwrap_or_default();
+        let (integer, fraction) = digits.split_at(digits.len() - precision);
+        (integer.to_string(), fraction.to_string())
+    }
+
+    fn group(integer: &str, separator: char) -> String {
+        let mut grouped = String::with_capacity(integer.len() + integer.len() / 3);
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i).is_multiple_of(3) {
+                grouped.push(separator);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+}
+
+/// How the values are shown in the cells of a table
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct CellFormat {
+    pub truncation: Truncation,
+    pub numbers: NumberFormat,
+}
+
+impl CellFormat {
+    pub const NULL: &str = "NULL";
+
+    /// Bytes of a binary value shown in its cell
+    const BINARY_PREVIEW_BYTES: usize = 16;
+
+    pub fn cell(&self, value: Option<&str>, kind: ColumnKind) -> String {
+        match (value, kind) {
+            (None, _) => Self::NULL.to_string(),
+            (Some(value), ColumnKind::Boolean) => Self::boolean(value),
+            (Some(value), ColumnKind::Integer | ColumnKind::Decimal) => {
+                self.truncation.apply(&self.numbers.apply(kind, value))
+            }
+            (Some(value), ColumnKind::Text | ColumnKind::Date | ColumnKind::Timestamp) => {
+                self.truncation.apply(value)
+            }
+            (Some(value), ColumnKind::Binary) => self.truncation.apply(&Self::binary(value)),
+        }
+    }
+
+    /// The first bytes and the size, e.g. `x'DEADBEEF' (4 bytes)`.
+    /// Values which are not hex literals are shown as they come
+    fn binary(value: &str) -> String {
+        let Some(hex) = binary_hex(value) else {
+            return value.to_string();
+        };
+
+        let bytes = hex.len() / 2;
+        let size = if bytes == 1 {
+            "1 byte".to_string()
+        } else {
+            format!("{bytes} bytes")
+        };
+        if bytes > Self::BINARY_PREVIEW_BYTES {
+            let preview = &hex[..Self::BINARY_PREVIEW_BYTES * 2];
+            if value.starts_with("x'") {
+                format!("x'{preview}...' ({size})")
+            } else {
+                // 0x or \x
+                format!("{}{preview}... ({size})", &value[..2])
+            }
+        } else {
+            format!("{value} ({size})")
+        }
+    }
+
+    /// Engines give booleans as true/false, t/f or 1/0
+    fn boolean(value: &str) -> String {
+        match value.trim().to_lowercase().as_str() {
+            "1" | "t" | "true" | "y" | "yes" => "true".to_string(),
+            "0" | "f" | "false" | "n" | "no" => "false".to_string(),
+            _ => value.to_string(),
+        }
+    }
+}
+
+/// Hex digits of a binary value written as `x'CAFE'` (SQLite), `0xCAFE` (MySQL and ODBC)
+/// or `\xcafe` (Postgres)
+pub fn binary_hex(value: &str) -> Option<&str> {
+    value
+        .strip_prefix("x'")
+        .and_then(|hex| hex.strip_suffix('\''))
+        .or_else(|| value.strip_prefix("0x"))
+        .or_else(|| value.strip_prefix("\\x"))
+        .filter(|hex| hex.len() % 2 == 0 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CellFormat, ColumnKind, NumberFormat};
+
+    #[test]
+    fn test_number_format() {
+        let format = NumberFormat {
+            thousands_separator: Some(','),
+            precision: Some(2),
+        };
+        assert_eq!(format.apply(ColumnKind::Integer, "-1234567"), "-1,234,567");
+        assert_eq!(format.apply(ColumnKind::Integer, "123"), "123");
+        assert_eq!(format.apply(ColumnKind::Decimal, "1234.5"), "1,234.50");
+        assert_eq!(format.apply(ColumnKind::Decimal, "999.995"), "1,000.00");
+        assert_eq!(format.apply(ColumnKind::Decimal, "0.004"), "0.00");
+        assert_eq!(format.apply(ColumnKind::Decimal, "1.5e-7"), "1.5e-7");
+
+        let format = NumberFormat {
+            thousands_separator: None,
+            precision: Some(0),
+        };
+        assert_eq!(format.apply(ColumnKind::Decimal, "2.5"), "3");
+        assert_eq!(
+            format.apply(ColumnKind::Decimal, "12345678901234567890.1"),
+            "12345678901234567890"
+        );
+    }
+
+    #[test]
+    fn test_cell() {
+        let format = CellFormat::default();
+        assert_eq!(format.cell(None, ColumnKind::Integer), "NULL");
+        assert_eq!(format.cell(Some("t"), ColumnKind::Boolean), "true");
+        assert_eq!(format.cell(Some("0"), ColumnKind::Boolean), "false");
+        assert_eq!(format.cell(Some("1234.50"), ColumnKind::Decimal), "1234.50");
+        assert_eq!(
+            format.cell(Some("x'DEADBEEF'"), ColumnKind::Binary),
+            "x'DEADBEEF' (4 bytes)"
+        );
+        assert_eq!(
+            format.cell(Some("x'FF'"), ColumnKind::Binary),
+            "x'FF' (1 byte)"
+        );
+        assert_eq!(
+            format.cell(Some(&format!("x'{}'", "00".repeat(20))), ColumnKind::Binary),
+            format!("x'{}...' (20 bytes)", "00".repeat(16))
+        );
+        assert_eq!(
+            format.cell(Some(&format!("0x{}", "AB".repeat(17))), ColumnKind::Binary),
+            format!("0x{}... (17 bytes)", "AB".repeat(16))
+        );
+        assert_eq!(format.cell(Some("text"), ColumnKind::Binary), "text");
+    }
+}
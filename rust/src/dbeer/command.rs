@@ -1,15 +1,40 @@
-use crate::dbeer::BorderStyle;
+use crate::dbeer::{BorderStyle, secret::redact};
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct Command {
     pub engine: String,
     pub conn_str: String,
     pub db_name: String,
     pub queries: String,
+    pub params: String,
+    pub output_format: String,
+    pub insert_table: String,
+    pub insert_batch: usize,
+    pub max_rows: usize,
+    pub page: usize,
+    pub max_cell_width: usize,
+    pub multiline: String,
+    pub thousands_separator: String,
+    pub decimal_precision: Option<usize>,
     pub border_style: BorderStyle,
     pub dest_folder: String,
     pub header_style_link: String,
     pub action: Action,
+    pub transaction: bool,
+    pub analyze: bool,
+    pub protocol: String,
+    /// 0 has no limit
+    pub timeout_ms: u64,
+    /// Name of the connection in the history
+    pub conn_name: String,
+    pub history_file: String,
+    /// Filters of the history action
+    pub search: String,
+    pub since: String,
+    pub until: String,
+    /// Name of the profile setting the connection, see `Profile`
+    pub profile: String,
+    pub profiles_file: String,
 }
 
 impl Command {
@@ -17,17 +42,80 @@ impl Command {
         Self {
             dest_folder: "/tmp".to_string(),
             header_style_link: "Type".to_string(),
+            insert_batch: 1,
+            page: 1,
+            max_cell_width: 100,
             ..Default::default()
         }
     }
+
+    /// Copy to be logged, without the passwords of the connection string
+    pub fn redacted(&self) -> Self {
+        Self {
+            conn_str: redact(&self.conn_str),
+            ..self.clone()
+        }
+    }
+
+    /// Sets the field of a `-flag value` argument.
+    /// Returns false when the flag is not a field of the command
+    pub fn set(&mut self, flag: &str, value: String) -> bool {
+        match flag {
+            "-engine" => self.engine = value,
+            "-conn-str" => self.conn_str = value,
+            "-dbname" => self.db_name = value,
+            "-queries" => self.queries = value,
+            "-params" => self.params = value,
+            "-output-format" => self.output_format = value,
+            "-insert-table" => self.insert_table = value,
+            "-max-rows" => self.max_rows = value.parse().unwrap_or(self.max_rows),
+            "-page" => self.page = value.parse().unwrap_or(self.page),
+            "-max-cell-width" => self.max_cell_width = value.parse().unwrap_or(self.max_cell_width),
+            "-multiline" => self.multiline = value,
+            "-thousands-separator" => self.thousands_separator = value,
+            "-decimal-precision" => self.decimal_precision = value.parse().ok(),
+            "-insert-batch" => self.insert_batch = value.parse().unwrap_or(self.insert_batch),
+            "-border-style" => self.border_style = value.into(),
+            "-dest-folder" => self.dest_folder = value,
+            "-option" => self.action = value.into(), // action in Lua
+            "-header-style-link" => self.header_style_link = value,
+            "-transaction" => self.transaction = value.parse().unwrap_or(self.transaction),
+            "-analyze" => self.analyze = value.parse().unwrap_or(self.analyze),
+            "-protocol" => self.protocol = value,
+            "-timeout-ms" => self.timeout_ms = value.parse().unwrap_or(self.timeout_ms),
+            "-conn-name" => self.conn_name = value,
+            "-history-file" => self.history_file = value,
+            "-search" => self.search = value,
+            "-since" => self.since = value,
+            "-until" => self.until = value,
+            "-profile" => self.profile = value,
+            "-profiles-file" => self.profiles_file = value,
+            _ => return false,
+        }
+        true
+    }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub enum Action {
     #[default]
     Run,
     Tables,
     TableInfo,
+    Explain,
+    History,
+}
+
+impl Action {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Run => "run",
+            Action::Tables => "tables",
+            Action::TableInfo => "table_info",
+            Action::Explain => "explain",
+            Action::History => "history",
+        }
+    }
 }
 
 impl From<String> for Action {
@@ -36,6 +124,8 @@ impl From<String> for Action {
             "1" => Action::Run,
             "2" => Action::Tables,
             "3" => Action::TableInfo,
+            "4" => Action::Explain,
+            "5" => Action::History,
             _ => unreachable!(),
         }
     }
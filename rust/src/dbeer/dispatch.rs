@@ -1,44 +1,162 @@
+use std::time::Duration;
+
 use super::command::Action;
 use crate::{
     dbeer::{
         self,
+        cancel::{self, Canceller},
+        column::{CellFormat, NumberFormat},
         command::Command,
         engine::{
-            Db2, Informix, Mongo, MsSql, MySql, Oracle, Postgres, Redis, SqlExecutor, Sqlite, Type,
+            Db2, Informix, Mongo, MsSql, MySql, Neo4j, Oracle, Postgres, Redis, SqlExecutor,
+            Sqlite, Type,
         },
-        query::{is_select_query, strip_sql_comments},
+        export::OutputFormat,
+        history,
+        insert::InsertScript,
+        params::Params,
+        query::{Multiline, Truncation, returns_rows, split_queries},
         table::Table,
     },
     dbeer_debug,
 };
 
+/// Open connection of an engine. `serve` keeps it between requests
+pub enum Connection {
+    Sql(Box<dyn SqlExecutor>),
+    Mongo(Mongo),
+    Redis(Redis),
+    Neo4j(Neo4j),
+}
+
+impl Connection {
+    #[allow(clippy::result_large_err)]
+    pub fn open(command: &Command, engine_type: Type) -> dbeer::Result<Self> {
+        let connection = match engine_type {
+            Type::Sql => {
+                let queries = &command.queries;
+                let params = Params::parse(&command.params)?;
+
+                let engine: Box<dyn SqlExecutor> = match command.engine.as_str() {
+                    "postgres" => Box::new(Postgres::connect(&command.conn_str, queries, params)?),
+                    "mysql" => Box::new(MySql::connect(
+                        &command.conn_str,
+                        queries,
+                        &command.db_name,
+                        params,
+                    )?),
+                    "informix" => Box::new(Informix::connect(&command.conn_str, queries, params)?),
+                    "mssql" => Box::new(MsSql::connect(&command.conn_str, queries, params)?),
+                    "oracle" => Box::new(Oracle::connect(&command.conn_str, queries, params)?),
+                    "db2" => Box::new(Db2::connect(&command.conn_str, queries, params)?),
+                    "sqlite" => Box::new(Sqlite::connect(&command.db_name, queries, params)?),
+                    not_supported => {
+                        return Err(dbeer::Error::Msg(format!(
+                            "Engine {not_supported} is not supported"
+                        )));
+                    }
+                };
+                Connection::Sql(engine)
+            }
+            Type::Mongo => Connection::Mongo(Mongo::connect(
+                &command.conn_str,
+                &command.db_name,
+                &command.queries,
+            )?),
+            Type::Redis => Connection::Redis(Redis::connect(&command.conn_str, &command.queries)?),
+            Type::Neo4j => Connection::Neo4j(Neo4j::connect(
+                &command.conn_str,
+                &command.db_name,
+                &command.queries,
+            )?),
+        };
+        Ok(connection)
+    }
+
+    /// Sets the statements of the command on an already open connection
+    #[allow(clippy::result_large_err)]
+    pub fn set_queries(&mut self, command: &Command) -> dbeer::Result {
+        match self {
+            Connection::Sql(engine) => {
+                engine.set_queries(&command.queries, Params::parse(&command.params)?)
+            }
+            Connection::Mongo(mongo) => mongo.set_queries(&command.queries),
+            Connection::Redis(redis) => redis.set_queries(&command.queries),
+            Connection::Neo4j(neo4j) => neo4j.set_queries(&command.queries),
+        }
+        Ok(())
+    }
+
+    /// `None` has no limit
+    #[allow(clippy::result_large_err)]
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) -> dbeer::Result {
+        match self {
+            Connection::Sql(engine) => engine.set_timeout(timeout)?,
+            Connection::Mongo(mongo) => mongo.set_timeout(timeout),
+            Connection::Redis(redis) => redis.set_timeout(timeout)?,
+            Connection::Neo4j(neo4j) => neo4j.set_timeout(timeout),
+        }
+        Ok(())
+    }
+
+    /// Only SQL engines cancel on the server
+    fn canceller(&self) -> Option<Canceller> {
+        match self {
+            Connection::Sql(engine) => engine.canceller(),
+            _ => None,
+        }
+    }
+}
+
 #[allow(clippy::result_large_err)]
 pub fn process(command: Command, engine_type: Type) -> dbeer::Result {
-    match engine_type {
-        Type::Sql => {
-            let queries = strip_sql_comments(&command.queries);
-
-            dbeer_debug!("Cleaned: {queries}");
-
-            let mut engine: Box<dyn SqlExecutor> = match command.engine.as_str() {
-                "postgres" => Box::new(Postgres::connect(&command.conn_str, &queries)?),
-                "mysql" => Box::new(MySql::connect(
-                    &command.conn_str,
-                    &queries,
-                    &command.db_name,
-                )?),
-                "informix" => Box::new(Informix::connect(&command.conn_str, &queries)?),
-                "mssql" => Box::new(MsSql::connect(&command.conn_str, &queries)?),
-                "oracle" => Box::new(Oracle::connect(&command.conn_str, &queries)?),
-                "db2" => Box::new(Db2::connect(&command.conn_str, &queries)?),
-                "sqlite" => Box::new(Sqlite::connect(&command.db_name, &queries)?),
-                not_supported => {
-                    return Err(dbeer::Error::Msg(format!(
-                        "Engine {not_supported} is not supported"
-                    )));
-                }
-            };
+    // The history is listed without opening a connection
+    if let Action::History = command.action {
+        return history::show(command);
+    }
+    let mut connection = Connection::open(&command, engine_type)?;
+    run(&mut connection, command)
+}
+
+/// Runs the command on the connection, which is open with the statements of the command.
+/// The running statement is cancelled on SIGINT or SIGTERM
+#[allow(clippy::result_large_err)]
+pub fn run(connection: &mut Connection, command: Command) -> dbeer::Result {
+    let timeout = (command.timeout_ms > 0).then(|| Duration::from_millis(command.timeout_ms));
+    connection.set_timeout(timeout)?;
+
+    cancel::set_canceller(connection.canceller());
+    let result = run_action(connection, command);
+    cancel::set_canceller(None);
+    result
+}
+
+#[allow(clippy::result_large_err)]
+fn run_action(connection: &mut Connection, command: Command) -> dbeer::Result {
+    let cell_format = CellFormat {
+        truncation: Truncation {
+            max_width: command.max_cell_width,
+            multiline: Multiline::parse(&command.multiline)?,
+        },
+        numbers: NumberFormat {
+            thousands_separator: command.thousands_separator.chars().next(),
+            precision: command.decimal_precision,
+        },
+    };
 
+    // Only the rows of SQL statements are exported, the rest is always shown as it is
+    let output_format = OutputFormat::parse(&command.output_format)?;
+    if output_format != OutputFormat::Table
+        && !(matches!(connection, Connection::Sql(_)) && matches!(command.action, Action::Run))
+    {
+        return Err(dbeer::Error::Msg(format!(
+            "Output format {} is only available running SQL statements",
+            output_format.extension()
+        )));
+    }
+
+    match connection {
+        Connection::Sql(engine) => {
             match command.action {
                 Action::Run => {
                     let table = &mut Table::new(
@@ -46,9 +164,40 @@ pub fn process(command: Command, engine_type: Type) -> dbeer::Result {
                         command.header_style_link,
                         command.border_style,
                     );
+                    table.output_format = output_format;
+                    table.cell_format = cell_format;
+                    table.max_rows = command.max_rows;
+                    table.page = command.page;
+
+                    let dialect = engine.dialect();
+                    // Comments are skipped by the splitter, which knows the quoting of the dialect
+                    let statements = split_queries(&command.queries, dialect);
+                    dbeer_debug!("Statements: {statements:#?}");
+
+                    if output_format == OutputFormat::Insert {
+                        table.insert_script = Some(InsertScript::new(
+                            dialect,
+                            &command.insert_table,
+                            statements.first().copied().unwrap_or_default(),
+                            command.insert_batch,
+                        )?);
+                    }
 
-                    if is_select_query(&queries) {
+                    if let [statement] = statements[..]
+                        && returns_rows(statement, dialect)
+                    {
+                        engine.set_queries(statement, Params::parse(&command.params)?);
                         engine.select(table)?;
+                    } else if command.transaction {
+                        engine.transaction(table)?;
+                    } else if statements.iter().any(|&query| returns_rows(query, dialect)) {
+                        if output_format != OutputFormat::Table {
+                            return Err(dbeer::Error::Msg(format!(
+                                "Output format {} needs a single row returning statement",
+                                output_format.extension()
+                            )));
+                        }
+                        engine.script(&statements, table)?;
                     } else {
                         engine.execute(table)?;
                     }
@@ -59,35 +208,66 @@ pub fn process(command: Command, engine_type: Type) -> dbeer::Result {
                     command.header_style_link,
                     command.border_style,
                 ))?,
+                Action::Explain => engine.explain(
+                    &mut Table::new(
+                        command.dest_folder,
+                        command.header_style_link,
+                        command.border_style,
+                    ),
+                    command.analyze,
+                )?,
+                Action::History => history::show(command)?,
             }
         }
-        Type::Mongo => {
-            let mongo = Mongo::connect(&command.conn_str, &command.db_name, &command.queries)?;
-
-            match command.action {
-                Action::Run => mongo.run(Table {
-                    dest_folder: command.dest_folder,
-                    ..Table::default()
-                })?,
-                Action::Tables => mongo.tables()?,
-                Action::TableInfo => {
-                    return Err(dbeer::Error::Msg(
-                        "Collection info not implemented for MongoDB".to_string(),
-                    ));
-                }
+        Connection::Mongo(mongo) => match command.action {
+            Action::Run => mongo.run(Table {
+                dest_folder: command.dest_folder,
+                max_rows: command.max_rows,
+                page: command.page,
+                ..Table::default()
+            })?,
+            Action::Tables => mongo.tables()?,
+            Action::TableInfo => {
+                return Err(dbeer::Error::Msg(
+                    "Collection info not implemented for MongoDB".to_string(),
+                ));
             }
-        }
-        Type::Redis => {
-            let mut redis = Redis::connect(&command.conn_str, &command.queries)?;
-
-            match command.action {
-                Action::Run => redis.run()?,
-                _ => {
-                    return Err(dbeer::Error::Msg("Not applicable for Redis".to_string()));
-                }
+            Action::Explain => {
+                return Err(dbeer::Error::Msg(
+                    "Explain not implemented for MongoDB".to_string(),
+                ));
             }
-        }
-        Type::Neo4j => return Err(dbeer::Error::Msg("Neo4j not implemented yet".to_string())),
+            Action::History => history::show(command)?,
+        },
+        Connection::Redis(redis) => match command.action {
+            Action::Run => redis.run()?,
+            Action::History => history::show(command)?,
+            _ => {
+                return Err(dbeer::Error::Msg("Not applicable for Redis".to_string()));
+            }
+        },
+        Connection::Neo4j(neo4j) => match command.action {
+            Action::Run => neo4j.run(&mut Table {
+                cell_format,
+                ..Table::new(
+                    command.dest_folder,
+                    command.header_style_link,
+                    command.border_style,
+                )
+            })?,
+            Action::Tables => neo4j.tables()?,
+            Action::TableInfo => neo4j.table_info(&mut Table::new(
+                command.dest_folder,
+                command.header_style_link,
+                command.border_style,
+            ))?,
+            Action::Explain => {
+                return Err(dbeer::Error::Msg(
+                    "Explain not implemented for Neo4j".to_string(),
+                ));
+            }
+            Action::History => history::show(command)?,
+        },
     }
 
     Ok(())
@@ -1,5 +1,9 @@
+use std::time::Duration;
+
 use crate::{
-    dbeer::{self, Table, engine::odbc::Odbc},
+    dbeer::{
+        self, Rows, Table, cancel::Canceller, engine::odbc::Odbc, params::Params, query::Dialect,
+    },
     dbeer_debug,
 };
 
@@ -9,9 +13,9 @@ pub struct Informix {
 
 impl Informix {
     #[allow(clippy::result_large_err)]
-    pub fn connect(conn_str: &str, queries: &str) -> dbeer::Result<Self> {
+    pub fn connect(conn_str: &str, queries: &str, params: Params) -> dbeer::Result<Self> {
         Ok(Self {
-            odbc: Odbc::new(conn_str, queries)?,
+            odbc: Odbc::new(conn_str, queries, params, Dialect::Informix)?,
         })
     }
 }
@@ -25,6 +29,22 @@ impl super::SqlExecutor for Informix {
         self.odbc.execute(table)
     }
 
+    fn fetch_rows(&mut self, query: &str, rows: &mut dyn Rows) -> dbeer::Result {
+        self.odbc.fetch_rows(query, rows)
+    }
+
+    fn affected_rows(&mut self, query: &str) -> dbeer::Result<u64> {
+        self.odbc.affected_rows(query)
+    }
+
+    fn transaction(&mut self, table: &mut Table) -> dbeer::Result {
+        self.odbc.transaction(table)
+    }
+
+    fn explain(&mut self, table: &mut Table, analyze: bool) -> dbeer::Result {
+        self.odbc.explain(table, analyze)
+    }
+
     fn tables(&mut self) -> dbeer::Result {
         self.odbc.queries =
             "SELECT tabname FROM systables WHERE tabtype = 'T' order by tabname;".to_string();
@@ -37,6 +57,22 @@ impl super::SqlExecutor for Informix {
         self.select(table)
     }
 
+    fn dialect(&self) -> Dialect {
+        self.odbc.dialect()
+    }
+
+    fn set_queries(&mut self, queries: &str, params: Params) {
+        self.odbc.set_queries(queries, params)
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> dbeer::Result {
+        self.odbc.set_timeout(timeout)
+    }
+
+    fn canceller(&self) -> Option<Canceller> {
+        self.odbc.canceller()
+    }
+
     fn table_info_query(&self) -> String {
         format!(
             r#"SELECT
@@ -15,12 +15,22 @@ pub use informix::Informix;
 pub use mongo::Mongo;
 pub use mssql::MsSql;
 pub use mysql::MySql;
+pub use neo4j::Neo4j;
 pub use oracle::Oracle;
 pub use postgres::Postgres;
 pub use redis::Redis;
 pub use sqlite::Sqlite;
 
-use crate::dbeer::{self, table::Table};
+use std::fmt::Display;
+use std::time::Duration;
+
+use crate::dbeer::{
+    self, Page, ResultSet, Rows, Section,
+    cancel::Canceller,
+    params::Params,
+    query::{Dialect, StatementKind, classify, is_dml, split_queries, truncate_field_string},
+    table::Table,
+};
 
 pub enum Type {
     Sql,
@@ -47,6 +57,79 @@ pub trait SqlExecutor {
     #[allow(clippy::result_large_err)]
     fn execute(&mut self, table: &mut Table) -> dbeer::Result;
 
+    /// Runs a single row returning statement sending every row to `rows`
+    /// as soon as it is fetched
+    #[allow(clippy::result_large_err)]
+    fn fetch_rows(&mut self, query: &str, rows: &mut dyn Rows) -> dbeer::Result;
+
+    /// Runs a single row returning statement keeping every row in memory
+    #[allow(clippy::result_large_err)]
+    fn result_set(&mut self, query: &str) -> dbeer::Result<ResultSet> {
+        let mut result_set = ResultSet::default();
+        self.fetch_rows(query, &mut result_set)?;
+        Ok(result_set)
+    }
+
+    /// Runs a single statement returning the number of affected rows
+    #[allow(clippy::result_large_err)]
+    fn affected_rows(&mut self, query: &str) -> dbeer::Result<u64>;
+
+    /// Runs every statement of a script in order. Each row returning statement
+    /// gets its own table and the rest a status line, failures included
+    #[allow(clippy::result_large_err)]
+    fn script(&mut self, queries: &[&str], table: &mut Table) -> dbeer::Result {
+        let dialect = self.dialect();
+        let mut sections = Vec::with_capacity(queries.len());
+
+        for (i, &query) in queries.iter().enumerate() {
+            let section = match classify(query, dialect) {
+                // A call without a result set has no columns
+                kind @ (StatementKind::Rows | StatementKind::Call) => {
+                    let mut result_set = ResultSet::default();
+                    let mut page = Page::new(&mut result_set, table.max_rows, table.page);
+                    let fetched = self.fetch_rows(query, &mut page).map(|()| page.footer());
+                    match fetched {
+                        Ok(_) if kind == StatementKind::Call && result_set.names.is_empty() => {
+                            Section::Status(format!("{})   Statement executed correctly.", i + 1))
+                        }
+                        Ok(footer) => Section::Rows {
+                            title: format!(
+                                "{})   {}",
+                                i + 1,
+                                truncate_field_string(
+                                    query.split_whitespace().collect::<Vec<_>>().join(" ")
+                                )
+                            ),
+                            footer,
+                            result_set,
+                        },
+                        Err(e) => Section::Status(format!("{})   {e}", i + 1)),
+                    }
+                }
+                kind => Section::Status(match self.affected_rows(query) {
+                    Ok(affected) if kind == StatementKind::Dml => {
+                        format!("{})   Row(s) affected: {affected}", i + 1)
+                    }
+                    Ok(_) => format!("{})   Statement executed correctly.", i + 1),
+                    Err(e) => format!("{})   {e}", i + 1),
+                }),
+            };
+            sections.push(section);
+        }
+
+        table.create_sections_file(&sections)
+    }
+
+    /// Runs the whole batch in a single transaction.
+    /// Everything is rolled back when any statement fails
+    #[allow(clippy::result_large_err)]
+    fn transaction(&mut self, table: &mut Table) -> dbeer::Result;
+
+    /// Shows the plan of the query. With `analyze` the query is executed
+    /// (when the engine supports it) so the actual figures are included
+    #[allow(clippy::result_large_err)]
+    fn explain(&mut self, table: &mut Table, analyze: bool) -> dbeer::Result;
+
     #[allow(clippy::result_large_err)]
     fn tables(&mut self) -> dbeer::Result;
 
@@ -54,4 +137,64 @@ pub trait SqlExecutor {
     fn table_info(&mut self, table: &mut Table) -> dbeer::Result;
 
     fn table_info_query(&self) -> String;
+
+    fn dialect(&self) -> Dialect;
+
+    /// Replaces the statements and their parameters,
+    /// so the open connection runs another batch
+    fn set_queries(&mut self, queries: &str, params: Params);
+
+    /// Statements running longer than `timeout` are cancelled by the engine.
+    /// `None` has no limit
+    #[allow(clippy::result_large_err)]
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> dbeer::Result;
+
+    /// Cancels the running statement from another thread
+    fn canceller(&self) -> Option<Canceller>;
+}
+
+/// Plans are generated for exactly one statement
+#[allow(clippy::result_large_err)]
+pub fn single_statement(queries: &str, dialect: Dialect) -> dbeer::Result<&str> {
+    match split_queries(queries, dialect)[..] {
+        [query] => Ok(query),
+        _ => Err(dbeer::Error::Msg(
+            "Explain needs a single statement".to_string(),
+        )),
+    }
+}
+
+/// Executes the statements in order and stops at the first failure.
+/// Returns the result lines together with the number of the failed statement
+pub fn execute_until_failure<T: Display, E: Display>(
+    queries: &[&str],
+    dialect: Dialect,
+    mut execute: impl FnMut(&str) -> Result<T, E>,
+) -> (Vec<String>, Option<usize>) {
+    let mut results = Vec::new();
+    for (i, &query) in queries.iter().enumerate() {
+        match execute(query) {
+            Ok(affected) => {
+                if is_dml(query, dialect) {
+                    results.push(format!("{})   Row(s) affected: {affected}", i + 1));
+                } else {
+                    results.push(format!("{})   Statement executed correctly.", i + 1));
+                }
+            }
+            Err(e) => {
+                results.push(format!("{})   {e}", i + 1));
+                return (results, Some(i + 1));
+            }
+        }
+    }
+    (results, None)
+}
+
+pub fn transaction_outcome(failed: Option<usize>) -> String {
+    match failed {
+        Some(statement) => {
+            format!("Statement {statement} failed. Transaction rolled back.")
+        }
+        None => "Transaction committed.".to_string(),
+    }
 }
@@ -1,11 +1,17 @@
+use std::time::Duration;
+
 use mongodb::{
+    action::Action,
     bson::{Bson, Document, to_document},
     sync::{Client, Collection, Database},
 };
 use regex::Regex;
 use serde_json::Value;
 
-use crate::{dbeer, dbeer_debug};
+use crate::{
+    dbeer::{self, response},
+    dbeer_debug, dbeer_message,
+};
 
 #[derive(Debug, PartialEq, Eq)]
 enum SubFunction {
@@ -101,6 +107,8 @@ impl Function {
 pub struct Mongo {
     queries: String,
     database: Database,
+    /// maxTimeMS of the reads. Writes have no server side limit
+    timeout: Option<Duration>,
 }
 
 impl Mongo {
@@ -112,9 +120,18 @@ impl Mongo {
         Ok(Self {
             queries: queries.to_string(),
             database,
+            timeout: None,
         })
     }
 
+    pub fn set_queries(&mut self, queries: &str) {
+        self.queries = queries.to_string();
+    }
+
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
     #[allow(clippy::result_large_err)]
     pub fn run(&self, table: dbeer::Table) -> dbeer::Result {
         let parts: Vec<&str> = self.queries.split(".").collect();
@@ -146,32 +163,47 @@ impl Mongo {
 
         match &function {
             Function::Find(params, sub_function) => {
-                let cursor = match sub_function {
-                    SubFunction::Sort(sub_params) => collection
-                        .find(self.create_document(params)?)
-                        .sort(self.create_document(sub_params)?)
-                        .run()
-                        .map_err(dbeer::Error::Mongo)?,
-                    SubFunction::Skip(number) => collection
-                        .find(self.create_document(params)?)
-                        .skip(*number)
-                        .run()
-                        .map_err(dbeer::Error::Mongo)?,
-                    SubFunction::Limit(number) => collection
-                        .find(self.create_document(params)?)
-                        .limit(*number)
-                        .run()
-                        .map_err(dbeer::Error::Mongo)?,
-                    _ => collection
-                        .find(self.create_document(params)?)
-                        .run()
-                        .map_err(dbeer::Error::Mongo)?,
-                };
+                let mut find = collection
+                    .find(self.create_document(params)?)
+                    .optional(self.timeout, |find, timeout| find.max_time(timeout));
+                let (mut skip, mut limit) = (0, None);
+                match sub_function {
+                    SubFunction::Sort(sub_params) => {
+                        find = find.sort(self.create_document(sub_params)?)
+                    }
+                    SubFunction::Skip(number) => skip = *number,
+                    SubFunction::Limit(number) => limit = Some(number.unsigned_abs()),
+                    SubFunction::None => {}
+                }
+
+                // One document past the page tells whether there are more
+                let offset = (table.max_rows * table.page.saturating_sub(1)) as u64;
+                if table.max_rows > 0 {
+                    skip += offset;
+                    let page_limit = table.max_rows as u64 + 1;
+                    limit = Some(
+                        limit.map_or(page_limit, |l| l.saturating_sub(offset).min(page_limit)),
+                    );
+                }
+
+                if limit == Some(0) {
+                    dbeer_message!("  Query has returned 0 results.");
+                    return Ok(());
+                }
+                if let Some(limit) = limit {
+                    find = find.limit(limit as i64);
+                }
+                let cursor = find.skip(skip).run().map_err(dbeer::Error::Mongo)?;
 
                 dbeer_debug!("Cursor {:#?}", cursor);
 
                 let mut results = Vec::new();
+                let mut more = false;
                 for result in cursor {
+                    if table.max_rows > 0 && results.len() == table.max_rows {
+                        more = true;
+                        break;
+                    }
                     let json_str =
                         serde_json::to_string_pretty(&result.map_err(dbeer::Error::Mongo)?)
                             .map_err(dbeer::Error::Serde)?;
@@ -179,20 +211,31 @@ impl Mongo {
                 }
 
                 if results.is_empty() {
-                    println!("  Query has returned 0 results.");
+                    dbeer_message!("  Query has returned 0 results.");
                     return Ok(());
                 }
 
+                if more {
+                    results.push(format!(
+                        "  Documents {}-{} (page {}). More documents available, next page: {}",
+                        offset + 1,
+                        offset + results.len() as u64,
+                        table.page,
+                        table.page + 1
+                    ));
+                }
+
                 table.create_execute_result_file(dbeer::Format::Json(results))
             }
             Function::FindOne(params) => {
                 let document = collection
                     .find_one(self.create_document(params)?)
+                    .optional(self.timeout, |find, timeout| find.max_time(timeout))
                     .run()
                     .map_err(dbeer::Error::Mongo)?;
 
                 if document.is_none() {
-                    println!("  Query has returned 0 results.");
+                    dbeer_message!("  Query has returned 0 results.");
                     return Ok(());
                 }
 
@@ -204,10 +247,11 @@ impl Mongo {
             Function::CountDocuments(params) => {
                 let total = collection
                     .count_documents(self.create_document(params)?)
+                    .optional(self.timeout, |count, timeout| count.max_time(timeout))
                     .run()
                     .map_err(dbeer::Error::Mongo)?;
 
-                println!(
+                dbeer_message!(
                     "  Collection {} count: {} results.",
                     collection.name(),
                     total
@@ -224,7 +268,7 @@ impl Mongo {
                     .as_object_id()
                     .unwrap();
 
-                println!(
+                dbeer_message!(
                     "  Collection {}, document inserted with ID: {}",
                     collection.name(),
                     inserted
@@ -239,7 +283,7 @@ impl Mongo {
                     .map_err(dbeer::Error::Mongo)?
                     .deleted_count;
 
-                println!(
+                dbeer_message!(
                     "  Collection {}, deleted {} document(s)",
                     collection.name(),
                     deleted
@@ -256,7 +300,7 @@ impl Mongo {
                     .map_err(dbeer::Error::Mongo)?
                     .modified_count;
 
-                println!(
+                dbeer_message!(
                     "  Collection {}, updated {} document(s)",
                     collection.name(),
                     modified
@@ -288,7 +332,7 @@ impl Mongo {
                     .collect::<Vec<String>>()
                     .join(", ");
 
-                println!(
+                dbeer_message!(
                     "  Collection {}, documents inserted with ID(s): {}",
                     collection.name(),
                     inserted
@@ -303,7 +347,7 @@ impl Mongo {
                     .map_err(dbeer::Error::Mongo)?
                     .deleted_count;
 
-                println!(
+                dbeer_message!(
                     "  Collection {}, deleted {} document(s)",
                     collection.name(),
                     deleted
@@ -320,7 +364,7 @@ impl Mongo {
                     .map_err(dbeer::Error::Mongo)?
                     .modified_count;
 
-                println!(
+                dbeer_message!(
                     "  Collection {}, updated {} document(s)",
                     collection.name(),
                     modified
@@ -331,7 +375,7 @@ impl Mongo {
             Function::Drop => {
                 collection.drop().run().map_err(dbeer::Error::Mongo)?;
 
-                println!("  Collection {} dropped successfully.", collection.name());
+                dbeer_message!("  Collection {} dropped successfully.", collection.name());
 
                 Ok(())
             }
@@ -384,7 +428,7 @@ impl Mongo {
             .join(" ");
 
         dbeer_debug!("Table names: {collection_names}");
-        println!("[{collection_names}]");
+        response::table_names(&collection_names);
 
         Ok(())
     }
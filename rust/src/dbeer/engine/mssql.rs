@@ -1,5 +1,14 @@
+use std::time::Duration;
+
 use crate::{
-    dbeer::{self, Table, engine::odbc::Odbc},
+    dbeer::{
+        self, Rows, Table,
+        cancel::Canceller,
+        engine::{odbc::Odbc, single_statement},
+        params::Params,
+        plan::{Plan, PlanNode},
+        query::{Dialect, truncate_field_string},
+    },
     dbeer_debug,
 };
 
@@ -9,13 +18,51 @@ pub struct MsSql {
 
 impl MsSql {
     #[allow(clippy::result_large_err)]
-    pub fn connect(conn_str: &str, queries: &str) -> dbeer::Result<Self> {
+    pub fn connect(conn_str: &str, queries: &str, params: Params) -> dbeer::Result<Self> {
         Ok(Self {
-            odbc: Odbc::new(conn_str, queries)?,
+            odbc: Odbc::new(conn_str, queries, params, Dialect::MsSql)?,
         })
     }
 }
 
+impl MsSql {
+    /// Builds the plan from the rows returned under `SET SHOWPLAN_ALL ON`
+    fn showplan(names: &[String], rows: Vec<Vec<Option<String>>>) -> Plan {
+        let position = |name: &str| names.iter().position(|n| n.eq_ignore_ascii_case(name));
+        let get = |row: &[Option<String>], name: &str| {
+            position(name)
+                .and_then(|i| row.get(i).cloned().flatten())
+                .unwrap_or_default()
+        };
+
+        let nodes = rows
+            .iter()
+            .map(|row| {
+                let physical = get(row, "PhysicalOp");
+                let logical = get(row, "LogicalOp");
+                let operation = if physical.is_empty() {
+                    get(row, "StmtText")
+                } else if logical.is_empty() || logical == physical {
+                    format!("{physical} {}", get(row, "Argument"))
+                } else {
+                    format!("{physical} ({logical}) {}", get(row, "Argument"))
+                };
+
+                let mut node = PlanNode::new(0, &truncate_field_string(operation.trim()));
+                node.cost = get(row, "TotalSubtreeCost");
+                node.rows = get(row, "EstimateRows");
+                (
+                    get(row, "NodeId").parse().unwrap_or_default(),
+                    get(row, "Parent").parse().unwrap_or_default(),
+                    node,
+                )
+            })
+            .collect();
+
+        Plan::from_parents(nodes)
+    }
+}
+
 impl super::SqlExecutor for MsSql {
     fn select(&mut self, table: &mut Table) -> dbeer::Result {
         self.odbc.select(table)
@@ -25,6 +72,33 @@ impl super::SqlExecutor for MsSql {
         self.odbc.execute(table)
     }
 
+    fn fetch_rows(&mut self, query: &str, rows: &mut dyn Rows) -> dbeer::Result {
+        self.odbc.fetch_rows(query, rows)
+    }
+
+    fn affected_rows(&mut self, query: &str) -> dbeer::Result<u64> {
+        self.odbc.affected_rows(query)
+    }
+
+    fn transaction(&mut self, table: &mut Table) -> dbeer::Result {
+        self.odbc.transaction(table)
+    }
+
+    fn explain(&mut self, table: &mut Table, analyze: bool) -> dbeer::Result {
+        let query = single_statement(&self.odbc.queries, Dialect::MsSql)?.to_string();
+        if analyze {
+            dbeer_debug!("MS-SQL only shows the estimated plan");
+        }
+
+        // SHOWPLAN_ALL returns the plan instead of executing the query
+        self.odbc.run("SET SHOWPLAN_ALL ON")?;
+        let fetched = self.odbc.result_set(&query);
+        self.odbc.run("SET SHOWPLAN_ALL OFF")?;
+
+        let result_set = fetched?;
+        Self::showplan(&result_set.names, result_set.values).render(table)
+    }
+
     fn tables(&mut self) -> dbeer::Result {
         self.odbc.queries = "SELECT name AS table_name FROM sys.tables order by name;".to_string();
         self.odbc.tables()
@@ -36,6 +110,22 @@ impl super::SqlExecutor for MsSql {
         self.select(table)
     }
 
+    fn dialect(&self) -> Dialect {
+        self.odbc.dialect()
+    }
+
+    fn set_queries(&mut self, queries: &str, params: Params) {
+        self.odbc.set_queries(queries, params)
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> dbeer::Result {
+        self.odbc.set_timeout(timeout)
+    }
+
+    fn canceller(&self) -> Option<Canceller> {
+        self.odbc.canceller()
+    }
+
     fn table_info_query(&self) -> String {
         format!(
             r#"SELECT 
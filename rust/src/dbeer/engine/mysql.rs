@@ -1,114 +1,399 @@
-use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::LazyLock;
+use std::time::Duration;
 
-use mysql::{Params, Pool, PooledConn, prelude::Queryable};
+use mysql::{
+    Column, Params, Pool, PooledConn, QueryResult, TxOpts, Value,
+    consts::ColumnType,
+    prelude::{Protocol, Queryable},
+};
+use regex::Regex;
 
 use crate::{
     dbeer::{
-        self, Format, Header,
-        query::{is_insert_update_or_delete, split_queries, truncate_field_string},
+        self, Format, Rows,
+        cancel::Canceller,
+        column::ColumnKind,
+        engine::{execute_until_failure, single_statement, transaction_outcome},
+        params::{self, Param},
+        plan::{Plan, PlanNode},
+        query::{Dialect, is_dml, split_queries},
+        response,
+        secret::redact,
     },
-    dbeer_debug,
+    dbeer_debug, dbeer_message,
 };
 
 pub struct MySql {
     queries: String,
     db_name: String,
+    // Kept to open the connection which cancels a statement
+    pool: Pool,
     connection: PooledConn,
+    params: params::Params,
+    // A session timeout stays until it is reset
+    timeout_set: bool,
 }
 
 impl MySql {
     #[allow(clippy::result_large_err)]
-    pub fn connect(conn_str: &str, queries: &str, db_name: &str) -> dbeer::Result<Self> {
+    pub fn connect(
+        conn_str: &str,
+        queries: &str,
+        db_name: &str,
+        params: params::Params,
+    ) -> dbeer::Result<Self> {
         let pool = Pool::new(conn_str).map_err(dbeer::Error::MySql)?;
 
         Ok(Self {
             queries: queries.to_string(),
             db_name: db_name.to_string(),
+            params,
+            timeout_set: false,
             connection: pool.get_conn().map_err(|_| {
                 dbeer::Error::Msg(format!(
-                    "Error connecting MySQL. Connection string: {conn_str}"
+                    "Error connecting MySQL. Connection string: {}",
+                    redact(conn_str)
                 ))
             })?,
+            pool,
         })
     }
-}
 
-impl super::SqlExecutor for MySql {
-    fn select(&mut self, table: &mut dbeer::Table) -> dbeer::Result {
-        let results = self
-            .connection
-            .query_iter(&self.queries)
-            .map_err(dbeer::Error::MySql)?;
+    fn mysql_value(value: &Param) -> Value {
+        match value {
+            Param::Null => Value::NULL,
+            Param::Bool(v) => Value::Int(*v as i64),
+            Param::Int(v) => Value::Int(*v),
+            Param::Float(v) => Value::Double(*v),
+            Param::Text(v) => Value::Bytes(v.as_bytes().to_vec()),
+        }
+    }
 
-        let results_columns = results.columns().as_ref().to_vec();
-        let mut headers: HashMap<_, _> = results_columns
+    /// Returns the SQL to prepare with its parameters. `:name` placeholders are
+    /// left to the driver as `Params::Named`, `$n` and `?` are sent positionally
+    #[allow(clippy::result_large_err)]
+    fn statement(&self, sql: &str) -> dbeer::Result<(String, Params)> {
+        let (rewritten, values) = self.params.bind(sql, Dialect::MySql)?;
+        if values.is_empty() {
+            return Ok((sql.to_string(), Params::Empty));
+        }
+
+        Ok(match &self.params {
+            params::Params::Named(named) => (
+                sql.to_string(),
+                Params::Named(
+                    named
+                        .iter()
+                        .map(|(name, value)| (name.as_bytes().to_vec(), Self::mysql_value(value)))
+                        .collect(),
+                ),
+            ),
+            _ => (
+                rewritten,
+                Params::Positional(values.into_iter().map(Self::mysql_value).collect()),
+            ),
+        })
+    }
+
+    /// Prepares the parameters of the whole batch before any statement runs
+    #[allow(clippy::result_large_err)]
+    fn statements(&self, queries: &[&str]) -> dbeer::Result<Vec<(String, Params)>> {
+        let mut statements = Vec::with_capacity(queries.len());
+        for query in queries {
+            statements.push(self.statement(query)?);
+        }
+        Ok(statements)
+    }
+
+    const BINARY_CHARSET: u16 = 63;
+
+    /// Statements which commit the transaction before and after they run
+    fn commits_implicitly(sql: &str) -> bool {
+        let words = sql
+            .split_whitespace()
+            .take(2)
+            .map(str::to_uppercase)
+            .collect::<Vec<_>>();
+        match words.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            ["CREATE" | "DROP", "TEMPORARY"] => false,
+            [first, ..] => matches!(
+                first,
+                "ALTER"
+                    | "CREATE"
+                    | "DROP"
+                    | "RENAME"
+                    | "TRUNCATE"
+                    | "GRANT"
+                    | "REVOKE"
+                    | "LOCK"
+                    | "ANALYZE"
+                    | "OPTIMIZE"
+                    | "REPAIR"
+                    | "FLUSH"
+                    | "INSTALL"
+                    | "UNINSTALL"
+            ),
+            [] => false,
+        }
+    }
+
+    /// Last statement committed when statement `failed` (from 1) of a transaction fails.
+    /// The implicit commit of a DDL statement happens even when the DDL itself fails
+    fn committed(queries: &[&str], failed: usize) -> Option<usize> {
+        let ddl = queries[..failed]
             .iter()
-            .enumerate()
-            .map(|(i, c)| (i + 2, Header::new(c.name_str().as_ref())))
-            .collect();
+            .rposition(|query| Self::commits_implicitly(query))?;
+        let last = if ddl + 1 == failed { ddl } else { ddl + 1 };
+        (last > 0).then_some(last)
+    }
 
-        headers.insert(1, Header::row_counter());
+    /// Cell of a row. A SQL NULL is `None`, so it is told apart from the text "NULL"
+    fn cell(value: Option<&Value>, column: &Column) -> Option<String> {
+        match value {
+            None | Some(Value::NULL) => None,
+            Some(value) => Some(Self::value_to_string(value, column)),
+        }
+    }
 
-        let mut rows: Vec<Vec<String>> = Vec::new();
-        for (i, row) in results.enumerate() {
-            let row_value = row.map_err(dbeer::Error::MySql)?;
+    /// Renders a cell from its `Value` and the column metadata
+    fn value_to_string(value: &Value, column: &Column) -> String {
+        match value {
+            Value::NULL => "NULL".to_string(),
+            Value::Int(v) => v.to_string(),
+            Value::UInt(v) => v.to_string(),
+            // Formatted as f32, so FLOAT columns do not show the digits of a widened f64
+            Value::Float(v) => Self::float_to_string(*v, column.decimals()),
+            Value::Double(v) => Self::float_to_string(*v, column.decimals()),
+            Value::Date(year, month, day, hour, minutes, seconds, micros) => {
+                let date = format!("{year:04}-{month:02}-{day:02}");
+                match column.column_type() {
+                    ColumnType::MYSQL_TYPE_DATE | ColumnType::MYSQL_TYPE_NEWDATE => date,
+                    _ => format!(
+                        "{date} {hour:02}:{minutes:02}:{seconds:02}{}",
+                        Self::micros_to_string(*micros, column.decimals())
+                    ),
+                }
+            }
+            Value::Time(negative, days, hours, minutes, seconds, micros) => format!(
+                "{}{:02}:{minutes:02}:{seconds:02}{}",
+                if *negative { "-" } else { "" },
+                *days * 24 + *hours as u32,
+                Self::micros_to_string(*micros, column.decimals())
+            ),
+            Value::Bytes(bytes) => match column.column_type() {
+                ColumnType::MYSQL_TYPE_BIT => Self::bits_to_string(bytes, column.column_length()),
+                ColumnType::MYSQL_TYPE_GEOMETRY => Self::hex(bytes),
+                ColumnType::MYSQL_TYPE_STRING
+                | ColumnType::MYSQL_TYPE_VAR_STRING
+                | ColumnType::MYSQL_TYPE_VARCHAR
+                | ColumnType::MYSQL_TYPE_TINY_BLOB
+                | ColumnType::MYSQL_TYPE_MEDIUM_BLOB
+                | ColumnType::MYSQL_TYPE_LONG_BLOB
+                | ColumnType::MYSQL_TYPE_BLOB
+                    if column.character_set() == Self::BINARY_CHARSET =>
+                {
+                    Self::hex(bytes)
+                }
+                _ => match std::str::from_utf8(bytes) {
+                    Ok(text) => text.to_string(),
+                    Err(_) => Self::hex(bytes),
+                },
+            },
+        }
+    }
+
+    fn float_to_string<F: Display>(value: F, decimals: u8) -> String {
+        // 31 is the server's marker for "not fixed"
+        if decimals < 31 {
+            format!("{value:.0$}", decimals as usize)
+        } else {
+            value.to_string()
+        }
+    }
+
+    fn micros_to_string(micros: u32, decimals: u8) -> String {
+        match decimals {
+            1..=6 => format!(".{micros:06}")[..decimals as usize + 1].to_string(),
+            _ if micros > 0 => format!(".{micros:06}"),
+            _ => String::new(),
+        }
+    }
 
-            let mut columns = Vec::with_capacity(headers.len());
-            let id_column = format!(" #{}", i + 1);
-            let id_column_length = id_column.len() + 1;
-            columns.push(id_column);
+    fn bits_to_string(bytes: &[u8], length: u32) -> String {
+        let bits = bytes.iter().map(|b| format!("{b:08b}")).collect::<String>();
+        let length = (length as usize).clamp(1, bits.len().max(1));
+        format!("b'{}'", &bits[bits.len().saturating_sub(length)..])
+    }
+
+    fn hex(bytes: &[u8]) -> String {
+        format!(
+            "0x{}",
+            bytes.iter().map(|b| format!("{b:02X}")).collect::<String>()
+        )
+    }
 
-            let column_counter = headers.get_mut(&1).unwrap();
-            if column_counter.length < id_column_length {
-                column_counter.length = id_column_length;
+    fn column_kind(column: &Column) -> ColumnKind {
+        match column.column_type() {
+            // BOOL is an alias of TINYINT(1)
+            ColumnType::MYSQL_TYPE_TINY if column.column_length() == 1 => ColumnKind::Boolean,
+            ColumnType::MYSQL_TYPE_TINY
+            | ColumnType::MYSQL_TYPE_SHORT
+            | ColumnType::MYSQL_TYPE_INT24
+            | ColumnType::MYSQL_TYPE_LONG
+            | ColumnType::MYSQL_TYPE_LONGLONG
+            | ColumnType::MYSQL_TYPE_YEAR => ColumnKind::Integer,
+            ColumnType::MYSQL_TYPE_DECIMAL
+            | ColumnType::MYSQL_TYPE_NEWDECIMAL
+            | ColumnType::MYSQL_TYPE_FLOAT
+            | ColumnType::MYSQL_TYPE_DOUBLE => ColumnKind::Decimal,
+            ColumnType::MYSQL_TYPE_DATE | ColumnType::MYSQL_TYPE_NEWDATE => ColumnKind::Date,
+            ColumnType::MYSQL_TYPE_DATETIME | ColumnType::MYSQL_TYPE_TIMESTAMP => {
+                ColumnKind::Timestamp
+            }
+            ColumnType::MYSQL_TYPE_GEOMETRY => ColumnKind::Binary,
+            ColumnType::MYSQL_TYPE_STRING
+            | ColumnType::MYSQL_TYPE_VAR_STRING
+            | ColumnType::MYSQL_TYPE_VARCHAR
+            | ColumnType::MYSQL_TYPE_TINY_BLOB
+            | ColumnType::MYSQL_TYPE_MEDIUM_BLOB
+            | ColumnType::MYSQL_TYPE_LONG_BLOB
+            | ColumnType::MYSQL_TYPE_BLOB
+                if column.character_set() == Self::BINARY_CHARSET =>
+            {
+                ColumnKind::Binary
             }
+            _ => ColumnKind::Text,
+        }
+    }
 
-            for column_index in 0..results_columns.len() {
-                let value = row_value
-                    .get::<String, _>(column_index)
-                    .map(truncate_field_string)
-                    .unwrap_or("NULL".to_string());
-
-                columns.push(format!(" {value}"));
-                let column = headers.get_mut(&(column_index + 2)).unwrap();
-                let length = value.chars().count() + 2;
-                if column.length < length {
-                    column.length = length;
-                }
+    /// Rows are read from the connection while the result is iterated
+    #[allow(clippy::result_large_err)]
+    fn stream<P: Protocol>(
+        results: QueryResult<'_, '_, '_, P>,
+        rows: &mut dyn Rows,
+    ) -> dbeer::Result {
+        let results_columns = results.columns().as_ref().to_vec();
+        let names: Vec<String> = results_columns
+            .iter()
+            .map(|c| c.name_str().to_string())
+            .collect();
+        rows.columns(&names)?;
+        rows.column_kinds(
+            &results_columns
+                .iter()
+                .map(Self::column_kind)
+                .collect::<Vec<_>>(),
+        );
+
+        for row in results {
+            let row_value = row.map_err(dbeer::Error::MySql)?;
+            rows.row(
+                results_columns
+                    .iter()
+                    .enumerate()
+                    .map(|(column_index, result_column)| {
+                        Self::cell(row_value.as_ref(column_index), result_column)
+                    })
+                    .collect(),
+            )?;
+            // The rows left are read and discarded when the result is dropped
+            if rows.done() {
+                break;
             }
-            rows.push(columns);
         }
+        Ok(())
+    }
 
-        if rows.is_empty() {
-            println!("  Query has returned 0 results.");
-            return Ok(());
+    /// Parses `EXPLAIN FORMAT=TREE` output where every level is indented 4 spaces
+    fn tree_plan(tree: &str) -> Plan {
+        static COST: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(r"\s*\(cost=(?<cost>[^ )]+)(?: rows=(?<rows>[^ )]+))?[^)]*\)").unwrap()
+        });
+        static ACTUAL: LazyLock<Regex> =
+            LazyLock::new(|| Regex::new(r"\s*\(actual (?<actual>[^)]+)\)").unwrap());
+
+        let nodes = tree
+            .lines()
+            .filter(|line| line.trim_start().starts_with("->"))
+            .map(|line| {
+                let depth = (line.len() - line.trim_start().len()) / 4;
+                let line = line.trim_start().trim_start_matches("->");
+
+                let mut node = PlanNode::new(depth, &ACTUAL.replace(&COST.replace(line, ""), ""));
+                if let Some(caps) = COST.captures(line) {
+                    node.cost = caps["cost"].to_string();
+                    node.rows = caps.name("rows").map_or("", |m| m.as_str()).to_string();
+                }
+                if let Some(caps) = ACTUAL.captures(line) {
+                    node.actual = caps["actual"].to_string();
+                }
+                node
+            })
+            .collect();
+
+        Plan { nodes }
+    }
+}
+
+impl super::SqlExecutor for MySql {
+    #[allow(clippy::result_large_err)]
+    fn select(&mut self, table: &mut dbeer::Table) -> dbeer::Result {
+        let query = self.queries.clone();
+        table.show_rows(|rows| self.fetch_rows(&query, rows))
+    }
+
+    fn fetch_rows(&mut self, query: &str, rows: &mut dyn Rows) -> dbeer::Result {
+        let (query, params) = self.statement(query)?;
+
+        // Prepared statements use the binary protocol, plain queries the text one
+        if params == Params::Empty {
+            let results = self
+                .connection
+                .query_iter(&query)
+                .map_err(dbeer::Error::MySql)?;
+            Self::stream(results, rows)
+        } else {
+            let results = self
+                .connection
+                .exec_iter(&query, params)
+                .map_err(dbeer::Error::MySql)?;
+            Self::stream(results, rows)
         }
+    }
 
-        table.update_headers_and_rows(headers, rows)
+    fn affected_rows(&mut self, query: &str) -> dbeer::Result<u64> {
+        let (query, params) = self.statement(query)?;
+        self.connection
+            .exec_drop(&query, params)
+            .map_err(dbeer::Error::MySql)?;
+        Ok(self.connection.affected_rows())
     }
 
     fn execute(&mut self, table: &mut dbeer::Table) -> dbeer::Result {
-        let queries = split_queries(&self.queries);
+        let queries = split_queries(&self.queries, Dialect::MySql);
+        let statements = self.statements(&queries)?;
 
         if queries.len() == 1 {
             let query = queries[0];
+            let (sql, params) = &statements[0];
             self.connection
-                .exec_drop(query, Params::Empty)
+                .exec_drop(sql, params.clone())
                 .map_err(dbeer::Error::MySql)?;
 
-            if is_insert_update_or_delete(query) {
-                println!("  Row(s) affected: {}", self.connection.affected_rows());
+            if is_dml(query, Dialect::MySql) {
+                response::affected_rows(self.connection.affected_rows());
             } else {
-                println!("  Statement executed correctly.");
+                dbeer_message!("  Statement executed correctly.");
             }
             return Ok(());
         }
 
         let mut results = Vec::new();
-        for (i, &query) in queries.iter().enumerate() {
-            let msg = match self.connection.exec_drop(query, Params::Empty) {
+        for (i, (&query, (sql, params))) in queries.iter().zip(statements).enumerate() {
+            let msg = match self.connection.exec_drop(sql, params) {
                 Ok(_) => {
-                    if is_insert_update_or_delete(query) {
+                    if is_dml(query, Dialect::MySql) {
                         format!(
                             "{})   Row(s) affected: {}",
                             i + 1,
@@ -126,6 +411,60 @@ impl super::SqlExecutor for MySql {
         table.create_execute_result_file(Format::Standard(results))
     }
 
+    fn transaction(&mut self, table: &mut dbeer::Table) -> dbeer::Result {
+        let queries = split_queries(&self.queries, Dialect::MySql);
+        let statements = self.statements(&queries)?;
+        let mut transaction = self
+            .connection
+            .start_transaction(TxOpts::default())
+            .map_err(dbeer::Error::MySql)?;
+
+        let mut statements = statements.into_iter();
+        let (mut results, failed) = execute_until_failure(&queries, Dialect::MySql, |_| {
+            let (sql, params) = statements.next().unwrap();
+            transaction
+                .exec_drop(sql, params)
+                .map(|_| transaction.affected_rows())
+        });
+
+        if failed.is_some() {
+            transaction.rollback().map_err(dbeer::Error::MySql)?;
+        } else {
+            transaction.commit().map_err(dbeer::Error::MySql)?;
+        }
+        results.push(match failed.zip(failed.and_then(|f| Self::committed(&queries, f))) {
+            Some((failed, committed)) => format!(
+                "Statement {failed} failed. Statements 1-{committed} stay committed, MySQL commits DDL implicitly. The rest was rolled back."
+            ),
+            None => transaction_outcome(failed),
+        });
+
+        table.create_execute_result_file(Format::Standard(results))
+    }
+
+    fn explain(&mut self, table: &mut dbeer::Table, analyze: bool) -> dbeer::Result {
+        let (statement, params) =
+            self.statement(single_statement(&self.queries, Dialect::MySql)?)?;
+        let query = format!(
+            "EXPLAIN {} {statement}",
+            if analyze { "ANALYZE" } else { "FORMAT=TREE" },
+        );
+        dbeer_debug!("Explain query: {query}");
+
+        // ANALYZE executes the statement so any change is rolled back
+        let mut transaction = self
+            .connection
+            .start_transaction(TxOpts::default())
+            .map_err(dbeer::Error::MySql)?;
+        let tree: String = transaction
+            .exec_first(query, params)
+            .map_err(dbeer::Error::MySql)?
+            .unwrap_or_default();
+        transaction.rollback().map_err(dbeer::Error::MySql)?;
+
+        Self::tree_plan(&tree).render(table)
+    }
+
     fn tables(&mut self) -> dbeer::Result {
         let results = self
             .connection
@@ -144,7 +483,7 @@ impl super::SqlExecutor for MySql {
 
         let formatted = table_names.join(" ");
         dbeer_debug!("Table names: {formatted}");
-        println!("[{formatted}]");
+        response::table_names(&formatted);
 
         Ok(())
     }
@@ -155,6 +494,60 @@ impl super::SqlExecutor for MySql {
         self.select(table)
     }
 
+    fn dialect(&self) -> Dialect {
+        Dialect::MySql
+    }
+
+    fn set_queries(&mut self, queries: &str, params: params::Params) {
+        self.queries = queries.to_string();
+        self.params = params;
+    }
+
+    /// `max_execution_time` only limits SELECT statements.
+    /// MariaDB has `max_statement_time` in seconds instead.
+    /// Servers with neither run without timeout.
+    /// Without a timeout the one set by an earlier run goes back to the global value
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> dbeer::Result {
+        let (millis, seconds) = match timeout {
+            Some(timeout) => (
+                timeout.as_millis().to_string(),
+                (timeout.as_millis() as f64 / 1000.0).to_string(),
+            ),
+            None if self.timeout_set => ("DEFAULT".to_string(), "DEFAULT".to_string()),
+            None => return Ok(()),
+        };
+
+        if let Err(e) = self
+            .connection
+            .query_drop(format!("SET SESSION max_execution_time = {millis}"))
+        {
+            dbeer_debug!("MySQL max_execution_time not available: {e}");
+            if let Err(e) = self
+                .connection
+                .query_drop(format!("SET SESSION max_statement_time = {seconds}"))
+            {
+                dbeer_debug!("MySQL max_statement_time not available, no timeout is set: {e}");
+                return Ok(());
+            }
+        }
+        self.timeout_set = timeout.is_some();
+        Ok(())
+    }
+
+    /// Kills the running statement through another connection
+    fn canceller(&self) -> Option<Canceller> {
+        let pool = self.pool.clone();
+        let id = self.connection.connection_id();
+        Some(Box::new(move || {
+            if let Err(e) = pool
+                .get_conn()
+                .and_then(|mut connection| connection.query_drop(format!("KILL QUERY {id}")))
+            {
+                dbeer_debug!("MySQL KILL QUERY {id} failed: {e}");
+            }
+        }))
+    }
+
     fn table_info_query(&self) -> String {
         format!(
             r#"SELECT 
@@ -203,3 +596,125 @@ impl super::SqlExecutor for MySql {
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use mysql::{Column, Value, consts::ColumnType};
+
+    use crate::dbeer::{
+        Rows,
+        engine::MySql,
+        insert::{InsertRows, InsertScript},
+        query::Dialect,
+    };
+
+    #[test]
+    fn test_tree_plan() {
+        let plan = MySql::tree_plan(
+            "-> Nested loop inner join  (cost=0.70 rows=1) (actual time=0.030..0.033 rows=1 loops=1)
+    -> Table scan on a  (cost=0.35 rows=1)
+    -> Single-row index lookup on b using PRIMARY (id=a.b_id)  (cost=0.35 rows=1)",
+        );
+
+        assert_eq!(plan.nodes.len(), 3);
+        assert_eq!(plan.nodes[0].operation, "Nested loop inner join");
+        assert_eq!(plan.nodes[0].actual, "time=0.030..0.033 rows=1 loops=1");
+        assert_eq!(plan.nodes[2].depth, 1);
+        assert_eq!(
+            plan.nodes[2].operation,
+            "Single-row index lookup on b using PRIMARY (id=a.b_id)"
+        );
+        assert_eq!(plan.nodes[2].cost, "0.35");
+        assert_eq!(plan.nodes[2].rows, "1");
+    }
+
+    #[test]
+    fn test_value_to_string() {
+        let datetime = Column::new(ColumnType::MYSQL_TYPE_DATETIME);
+        assert_eq!(
+            MySql::value_to_string(&Value::Date(2024, 5, 1, 13, 4, 5, 0), &datetime),
+            "2024-05-01 13:04:05"
+        );
+
+        let date = Column::new(ColumnType::MYSQL_TYPE_DATE);
+        assert_eq!(
+            MySql::value_to_string(&Value::Date(2024, 5, 1, 0, 0, 0, 0), &date),
+            "2024-05-01"
+        );
+
+        let time = Column::new(ColumnType::MYSQL_TYPE_TIME);
+        assert_eq!(
+            MySql::value_to_string(&Value::Time(true, 1, 2, 3, 4, 0), &time),
+            "-26:03:04"
+        );
+
+        let blob = Column::new(ColumnType::MYSQL_TYPE_BLOB).with_character_set(63);
+        assert_eq!(
+            MySql::value_to_string(&Value::Bytes(vec![0xCA, 0xFE]), &blob),
+            "0xCAFE"
+        );
+
+        let text = Column::new(ColumnType::MYSQL_TYPE_VAR_STRING).with_character_set(255);
+        assert_eq!(
+            MySql::value_to_string(&Value::Bytes(b"NULL".to_vec()), &text),
+            "NULL"
+        );
+        assert_eq!(
+            MySql::value_to_string(&Value::Bytes(vec![0xFF, 0x00]), &text),
+            "0xFF00"
+        );
+
+        let bit = Column::new(ColumnType::MYSQL_TYPE_BIT).with_column_length(4);
+        assert_eq!(
+            MySql::value_to_string(&Value::Bytes(vec![0b0101]), &bit),
+            "b'0101'"
+        );
+
+        let decimal = Column::new(ColumnType::MYSQL_TYPE_NEWDECIMAL);
+        assert_eq!(
+            MySql::value_to_string(&Value::Bytes(b"12.50".to_vec()), &decimal),
+            "12.50"
+        );
+
+        let float = Column::new(ColumnType::MYSQL_TYPE_FLOAT).with_decimals(31);
+        assert_eq!(MySql::value_to_string(&Value::Float(0.1), &float), "0.1");
+        let float = Column::new(ColumnType::MYSQL_TYPE_FLOAT).with_decimals(2);
+        assert_eq!(MySql::value_to_string(&Value::Float(0.125), &float), "0.12");
+    }
+
+    #[test]
+    fn test_committed() {
+        let queries = [
+            "INSERT INTO t VALUES (1)",
+            "create table u (id int)",
+            "INSERT INTO u VALUES (2)",
+            "INSERT INTO missing VALUES (3)",
+        ];
+        assert_eq!(MySql::committed(&queries, 4), Some(2));
+        assert_eq!(MySql::committed(&queries, 2), Some(1));
+        assert_eq!(MySql::committed(&queries[..1], 1), None);
+        assert_eq!(
+            MySql::committed(&["CREATE TEMPORARY TABLE x (id int)", "bad"], 2),
+            None
+        );
+    }
+
+    #[test]
+    fn test_null_cell() {
+        let text = Column::new(ColumnType::MYSQL_TYPE_VAR_STRING).with_character_set(255);
+        let script = InsertScript::new(Dialect::MySql, "t", "", 10).unwrap();
+        let mut out = Vec::new();
+        let mut rows = InsertRows::new(script, &mut out);
+        rows.columns(&["a".to_string(), "b".to_string()]).unwrap();
+        rows.row(vec![
+            MySql::cell(Some(&Value::NULL), &text),
+            MySql::cell(Some(&Value::Bytes(b"NULL".to_vec())), &text),
+        ])
+        .unwrap();
+        rows.finish().unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "INSERT INTO `t` (`a`, `b`) VALUES (NULL, 'NULL');\n"
+        );
+    }
+}
@@ -1,4 +1,5 @@
 #[allow(clippy::module_inception)]
 mod odbc;
+mod value;
 
 pub use odbc::Odbc;
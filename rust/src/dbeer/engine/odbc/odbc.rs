@@ -1,163 +1,298 @@
-use std::collections::HashMap;
+use std::cell::OnceCell;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use odbc::{
-    Environment,
+    Allocated, Connection, Environment, Handle, NoResult,
     ResultSetState::{Data, NoData},
-    Statement, Version3, create_environment_v3,
+    Statement, Version3, create_environment_v3, ffi,
+    safe::{AutocommitMode, AutocommitOn},
 };
 
 use crate::{
     dbeer::{
-        self, Header, Table,
-        engine::SqlExecutor,
-        query::{is_insert_update_or_delete, split_queries, truncate_field_string},
+        self, Rows, Table,
+        cancel::Canceller,
+        column::ColumnKind,
+        engine::{
+            SqlExecutor, execute_until_failure,
+            odbc::value::{self, Kind},
+            transaction_outcome,
+        },
+        params::{Param, Params},
+        query::{Dialect, is_dml, split_queries},
+        response,
     },
-    dbeer_debug,
+    dbeer_debug, dbeer_message,
 };
 
+/// Not in the attributes of odbc-sys
+const SQL_ATTR_QUERY_TIMEOUT: ffi::SQLINTEGER = 0;
+
+// Declared with a plain integer attribute to pass SQL_ATTR_QUERY_TIMEOUT
+#[allow(clashing_extern_declarations)]
+unsafe extern "system" {
+    fn SQLSetStmtAttr(
+        hstmt: ffi::SQLHSTMT,
+        attr: ffi::SQLINTEGER,
+        value: ffi::SQLPOINTER,
+        str_length: ffi::SQLINTEGER,
+    ) -> ffi::SQLRETURN;
+}
+
+/// Handle of the statement being executed, read by the canceller from another thread
+type Running = Arc<Mutex<Option<usize>>>;
+
+/// Sets the running statement until it is dropped
+struct RunningStatement<'a>(&'a Mutex<Option<usize>>);
+
+impl<'a> RunningStatement<'a> {
+    fn new<S: Handle<To = ffi::Stmt>>(running: &'a Mutex<Option<usize>>, stmt: &S) -> Self {
+        // SAFETY: the handle is only copied
+        let handle = unsafe { stmt.handle() } as usize;
+        if let Ok(mut running) = running.lock() {
+            *running = Some(handle);
+        }
+        Self(running)
+    }
+}
+
+impl Drop for RunningStatement<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut running) = self.0.lock() {
+            *running = None;
+        }
+    }
+}
+
+thread_local! {
+    static ENVIRONMENT: OnceCell<&'static Environment<Version3>> = const { OnceCell::new() };
+}
+
 pub struct Odbc {
     pub queries: String,
-    conn_str: String,
-    environment: Environment<Version3>,
+    params: Params,
+    dialect: Dialect,
+    timeout: Option<Duration>,
+    running: Running,
+    // Only taken while a transaction disables autocommit
+    connection: Option<Connection<'static, AutocommitOn>>,
 }
 
 impl Odbc {
     #[allow(clippy::result_large_err)]
-    pub fn new(conn_str: &str, queries: &str) -> dbeer::Result<Self> {
-        let environment = create_environment_v3().map_err(|e| dbeer::Error::Odbc(e.unwrap()))?;
+    pub fn new(
+        conn_str: &str,
+        queries: &str,
+        params: Params,
+        dialect: Dialect,
+    ) -> dbeer::Result<Self> {
+        let connection = Self::environment()?
+            .connect_with_connection_string(conn_str)
+            .map_err(dbeer::Error::Odbc)?;
+
         Ok(Self {
             queries: queries.to_string(),
-            conn_str: conn_str.to_string(),
-            environment,
+            params,
+            dialect,
+            timeout: None,
+            running: Running::default(),
+            connection: Some(connection),
+        })
+    }
+
+    /// Connections borrow the environment, so it is created once
+    /// and lives for the whole process
+    #[allow(clippy::result_large_err)]
+    fn environment() -> dbeer::Result<&'static Environment<Version3>> {
+        ENVIRONMENT.with(|cell| {
+            if let Some(environment) = cell.get() {
+                return Ok(*environment);
+            }
+
+            let environment: &'static Environment<Version3> = Box::leak(Box::new(
+                create_environment_v3().map_err(|e| dbeer::Error::Odbc(e.unwrap()))?,
+            ));
+            Ok(*cell.get_or_init(|| environment))
         })
     }
+
+    #[allow(clippy::result_large_err)]
+    fn connection(&self) -> dbeer::Result<&Connection<'static, AutocommitOn>> {
+        self.connection
+            .as_ref()
+            .ok_or_else(|| dbeer::Error::Msg("ODBC connection is not available".to_string()))
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn run(&self, query: &str) -> dbeer::Result<i64> {
+        let (query, values) = self.params.bind(query, self.dialect)?;
+        Self::execute_statement(
+            self.connection()?,
+            &query,
+            &values,
+            self.timeout,
+            &self.running,
+        )
+        .map_err(dbeer::Error::Odbc)
+    }
+
+    /// New statement with the query timeout, in whole seconds rounded up.
+    /// Drivers without query timeout support only log it
+    #[allow(clippy::result_large_err)]
+    fn statement<'a, 'b, AC: AutocommitMode>(
+        connection: &'a Connection<'static, AC>,
+        timeout: Option<Duration>,
+    ) -> odbc::Result<Statement<'a, 'b, Allocated, NoResult, AC>> {
+        let stmt = Statement::with_parent(connection)?;
+        if let Some(timeout) = timeout {
+            let seconds = timeout.as_millis().div_ceil(1000) as usize;
+            // SAFETY: the handle belongs to the statement just allocated
+            // and the attribute value is passed as an integer, not a pointer
+            let ret = unsafe {
+                SQLSetStmtAttr(
+                    stmt.handle(),
+                    SQL_ATTR_QUERY_TIMEOUT,
+                    seconds as ffi::SQLPOINTER,
+                    0,
+                )
+            };
+            if ret != ffi::SQL_SUCCESS && ret != ffi::SQL_SUCCESS_WITH_INFO {
+                dbeer_debug!("ODBC driver has not set the query timeout: {ret:?}");
+            }
+        }
+        Ok(stmt)
+    }
+
+    /// Binds the values in placeholder order. NULL is bound as an empty `Option`
+    #[allow(clippy::result_large_err)]
+    fn bind<'a, 'b, AC: AutocommitMode>(
+        mut stmt: Statement<'a, 'b, Allocated, NoResult, AC>,
+        values: &[&'b Param],
+    ) -> odbc::Result<Statement<'a, 'b, Allocated, NoResult, AC>> {
+        const NULL: Option<i64> = None;
+
+        for (i, &value) in values.iter().enumerate() {
+            let index = i as u16 + 1;
+            stmt = match value {
+                Param::Null => stmt.bind_parameter(index, &NULL)?,
+                Param::Bool(v) => stmt.bind_parameter(index, v)?,
+                Param::Int(v) => stmt.bind_parameter(index, v)?,
+                Param::Float(v) => stmt.bind_parameter(index, v)?,
+                Param::Text(v) => stmt.bind_parameter(index, v)?,
+            };
+        }
+        Ok(stmt)
+    }
+
+    /// Runs a single statement returning the number of affected rows
+    #[allow(clippy::result_large_err)]
+    fn execute_statement<AC: AutocommitMode>(
+        connection: &Connection<'static, AC>,
+        query: &str,
+        values: &[&Param],
+        timeout: Option<Duration>,
+        running: &Mutex<Option<usize>>,
+    ) -> odbc::Result<i64> {
+        let stmt = Self::bind(Self::statement(connection, timeout)?, values)?;
+        let _running = RunningStatement::new(running, &stmt);
+        match stmt.exec_direct(query)? {
+            Data(stmt) => stmt.affected_row_count(),
+            NoData(stmt) => stmt.affected_row_count(),
+        }
+    }
 }
 
 impl SqlExecutor for Odbc {
+    #[allow(clippy::result_large_err)]
     fn select(&mut self, table: &mut Table) -> dbeer::Result {
-        let connection = self
-            .environment
-            .connect_with_connection_string(&self.conn_str)
-            .map_err(dbeer::Error::Odbc)?;
-
-        let stmt = Statement::with_parent(&connection).map_err(dbeer::Error::Odbc)?;
-        let mut headers = HashMap::new();
-        headers.insert(1, Header::row_counter());
-        let mut rows: Vec<Vec<String>> = Vec::new();
+        let query = self.queries.clone();
+        table.show_rows(|rows| self.fetch_rows(&query, rows))
+    }
 
-        match stmt
-            .exec_direct(&self.queries)
-            .map_err(dbeer::Error::Odbc)?
-        {
-            Data(mut stmt) => {
-                let columns_len = stmt.num_result_cols().map_err(dbeer::Error::Odbc)?;
-
-                for i in 1..=columns_len {
-                    headers.insert(
-                        (i + 1) as usize,
-                        Header::new(
-                            &stmt
-                                .describe_col(i as u16)
-                                .map_err(dbeer::Error::Odbc)?
-                                .name
-                                .to_uppercase(),
-                        ),
-                    );
-                }
+    /// Rows are read from the cursor one at a time. A `None` value is a SQL NULL
+    fn fetch_rows(&mut self, query: &str, rows: &mut dyn Rows) -> dbeer::Result {
+        let (query, values) = self.params.bind(query, self.dialect)?;
+        let stmt = Self::statement(self.connection()?, self.timeout).map_err(dbeer::Error::Odbc)?;
+        let stmt = Self::bind(stmt, &values).map_err(dbeer::Error::Odbc)?;
+        let _running = RunningStatement::new(&self.running, &stmt);
 
-                let mut col_counter = 1;
+        if let Data(mut stmt) = stmt.exec_direct(&query).map_err(dbeer::Error::Odbc)? {
+            let columns_len = stmt.num_result_cols().map_err(dbeer::Error::Odbc)? as u16;
+            let mut names = Vec::with_capacity(columns_len as usize);
+            let mut kinds = Vec::with_capacity(columns_len as usize);
+            let mut column_kinds = Vec::with_capacity(columns_len as usize);
 
-                while let Some(mut cursor) = stmt.fetch().map_err(dbeer::Error::Odbc)? {
-                    let mut columns = Vec::with_capacity(headers.len());
-                    let id_column = format!(" #{col_counter}");
-                    let id_column_length = id_column.len() + 1;
-                    columns.push(id_column);
+            for i in 1..=columns_len {
+                let descriptor = stmt.describe_col(i).map_err(dbeer::Error::Odbc)?;
+                dbeer_debug!(
+                    "ODBC column {} has type {:?}",
+                    descriptor.name,
+                    descriptor.data_type
+                );
+                kinds.push(Kind::from(&descriptor));
+                column_kinds.push(ColumnKind::from(&descriptor));
+                names.push(descriptor.name.to_uppercase());
+            }
+            rows.columns(&names)?;
+            rows.column_kinds(&column_kinds);
 
-                    let column_counter = headers.get_mut(&1).unwrap();
-                    if column_counter.length < id_column_length {
-                        column_counter.length = id_column_length;
-                    }
-                    col_counter += 1;
-
-                    for i in 1..=columns_len {
-                        let value = if let Some(value) = cursor
-                            .get_data::<String>(i as u16)
-                            .map_err(dbeer::Error::Odbc)?
-                        {
-                            let value = truncate_field_string(value);
-                            columns.push(format!(" {value}"));
-                            value
-                        } else {
-                            columns.push(" NULL".to_string());
-                            "NULL".to_string()
-                        };
-
-                        let column = headers.get_mut(&(i as usize + 1)).unwrap();
-                        let length = value.chars().count() + 2;
-                        if column.length < length {
-                            column.length = length;
-                        }
-                    }
-                    rows.push(columns);
+            while let Some(mut cursor) = stmt.fetch().map_err(dbeer::Error::Odbc)? {
+                let mut values = Vec::with_capacity(kinds.len());
+                for (i, kind) in (1..=columns_len).zip(kinds.iter().copied()) {
+                    values.push(value::read(&mut cursor, i, kind).map_err(dbeer::Error::Odbc)?);
+                }
+                rows.row(values)?;
+                if rows.done() {
+                    break;
                 }
             }
-            NoData(_) => {
-                println!("  Query has returned 0 results.");
-                return Ok(());
-            }
-        }
-
-        if rows.is_empty() {
-            println!("  Query has returned 0 results.");
-            return Ok(());
         }
+        Ok(())
+    }
 
-        table.update_headers_and_rows(headers, rows)
+    fn affected_rows(&mut self, query: &str) -> dbeer::Result<u64> {
+        // Drivers report -1 when the count is not available
+        Ok(u64::try_from(self.run(query)?).unwrap_or_default())
     }
 
     fn execute(&mut self, table: &mut Table) -> dbeer::Result {
-        let queries = split_queries(&self.queries);
+        let queries = split_queries(&self.queries, self.dialect);
+        let statements = self.params.bind_all(&queries, self.dialect)?;
 
         if queries.len() == 1 {
             let query = queries[0];
-            let connection = self
-                .environment
-                .connect_with_connection_string(&self.conn_str)
-                .map_err(dbeer::Error::Odbc)?;
-
-            let stmt = Statement::with_parent(&connection).map_err(dbeer::Error::Odbc)?;
-            let affected_rows = match stmt
-                .exec_direct(&self.queries)
-                .map_err(dbeer::Error::Odbc)?
-            {
-                Data(stmt) => stmt.affected_row_count().map_err(dbeer::Error::Odbc)?,
-                NoData(stmt) => stmt.affected_row_count().map_err(dbeer::Error::Odbc)?,
-            };
+            let (sql, values) = &statements[0];
+            let affected_rows = Self::execute_statement(
+                self.connection()?,
+                sql,
+                values,
+                self.timeout,
+                &self.running,
+            )
+            .map_err(dbeer::Error::Odbc)?;
 
-            if is_insert_update_or_delete(query) {
-                println!("  Row(s) affected: {affected_rows}");
+            if is_dml(query, self.dialect) {
+                // Drivers report -1 when the count is not available
+                response::affected_rows(u64::try_from(affected_rows).unwrap_or_default());
             } else {
-                println!("  Statement executed correctly.");
+                dbeer_message!("  Statement executed correctly.");
             }
             return Ok(());
         }
 
         let mut results = Vec::new();
-        for (i, &query) in queries.iter().enumerate() {
-            let connection = self
-                .environment
-                .connect_with_connection_string(&self.conn_str)
-                .map_err(dbeer::Error::Odbc)?;
-
-            let stmt = Statement::with_parent(&connection).map_err(dbeer::Error::Odbc)?;
-
-            let msg = match stmt.exec_direct(&self.queries) {
-                Ok(data) => {
-                    let affected_rows = match data {
-                        Data(stmt) => stmt.affected_row_count().map_err(dbeer::Error::Odbc)?,
-                        NoData(stmt) => stmt.affected_row_count().map_err(dbeer::Error::Odbc)?,
-                    };
-                    if is_insert_update_or_delete(query) {
+        for (i, (&query, (sql, values))) in queries.iter().zip(&statements).enumerate() {
+            let executed = Self::execute_statement(
+                self.connection()?,
+                sql,
+                values,
+                self.timeout,
+                &self.running,
+            );
+            let msg = match executed {
+                Ok(affected_rows) => {
+                    if is_dml(query, self.dialect) {
                         format!("{})   Row(s) affected: {}", i + 1, affected_rows)
                     } else {
                         format!("{})   Statement executed correctly.", i + 1)
@@ -172,13 +307,49 @@ impl SqlExecutor for Odbc {
         table.create_execute_result_file(dbeer::Format::Standard(results))
     }
 
-    fn tables(&mut self) -> crate::dbeer::Result {
+    #[allow(clippy::result_large_err)]
+    fn transaction(&mut self, table: &mut Table) -> dbeer::Result {
+        let queries = split_queries(&self.queries, self.dialect);
+        let statements = self.params.bind_all(&queries, self.dialect)?;
         let connection = self
-            .environment
-            .connect_with_connection_string(&self.conn_str)
-            .map_err(dbeer::Error::Odbc)?;
+            .connection
+            .take()
+            .ok_or_else(|| dbeer::Error::Msg("ODBC connection is not available".to_string()))?;
+        let mut connection = connection.disable_autocommit().map_err(|connection| {
+            self.connection = Some(connection);
+            dbeer::Error::Msg("ODBC driver could not disable autocommit".to_string())
+        })?;
+
+        let mut statements = statements.iter();
+        let (mut results, failed) = execute_until_failure(&queries, self.dialect, |_| {
+            let (sql, values) = statements.next().unwrap();
+            Self::execute_statement(&connection, sql, values, self.timeout, &self.running)
+        });
+
+        let ended = if failed.is_some() {
+            connection.rollback()
+        } else {
+            connection.commit()
+        };
 
-        let stmt = Statement::with_parent(&connection).map_err(dbeer::Error::Odbc)?;
+        self.connection = Some(connection.enable_autocommit().map_err(|_| {
+            dbeer::Error::Msg("ODBC driver could not enable autocommit".to_string())
+        })?);
+        ended.map_err(dbeer::Error::Odbc)?;
+        results.push(transaction_outcome(failed));
+
+        table.create_execute_result_file(dbeer::Format::Standard(results))
+    }
+
+    fn explain(&mut self, _table: &mut Table, _analyze: bool) -> dbeer::Result {
+        Err(dbeer::Error::Msg(
+            "Explain is not supported for this engine".to_string(),
+        ))
+    }
+
+    fn tables(&mut self) -> crate::dbeer::Result {
+        let stmt = Self::statement(self.connection()?, self.timeout).map_err(dbeer::Error::Odbc)?;
+        let _running = RunningStatement::new(&self.running, &stmt);
         let mut table_names = String::new();
 
         if let Data(mut stmt) = stmt
@@ -199,7 +370,7 @@ impl SqlExecutor for Odbc {
         }
 
         dbeer_debug!("Table names: {table_names}");
-        println!("[{table_names}]");
+        response::table_names(&table_names);
 
         Ok(())
     }
@@ -208,6 +379,41 @@ impl SqlExecutor for Odbc {
         unimplemented!()
     }
 
+    fn dialect(&self) -> Dialect {
+        self.dialect
+    }
+
+    fn set_queries(&mut self, queries: &str, params: Params) {
+        self.queries = queries.to_string();
+        self.params = params;
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> dbeer::Result {
+        self.timeout = timeout;
+        Ok(())
+    }
+
+    /// Cancels the statement being executed, if any
+    fn canceller(&self) -> Option<Canceller> {
+        let running = Arc::clone(&self.running);
+        Some(Box::new(move || {
+            // The lock keeps the statement from being marked as done while it is cancelled
+            let Ok(running) = running.lock() else {
+                return;
+            };
+            let Some(stmt) = *running else {
+                dbeer_debug!("ODBC has no statement running to cancel");
+                return;
+            };
+            // SAFETY: the handle is set from just before the statement is executed
+            // until it is done. The driver manager rejects a handle already freed
+            let ret = unsafe { ffi::SQLCancel(stmt as ffi::SQLHSTMT) };
+            if ret != ffi::SQL_SUCCESS && ret != ffi::SQL_SUCCESS_WITH_INFO {
+                dbeer_debug!("ODBC driver has not cancelled the statement: {ret:?}");
+            }
+        }))
+    }
+
     fn table_info_query(&self) -> String {
         unimplemented!()
     }
@@ -0,0 +1,206 @@
+use odbc::{
+    ColumnDescriptor, Cursor, SqlDate, SqlSsTime2, SqlTime, SqlTimestamp, ffi::SqlDataType,
+    safe::AutocommitOn,
+};
+
+use crate::dbeer::column::ColumnKind;
+
+/// How a column is read from the driver, chosen from the SQL type
+/// returned by `describe_col`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Integer,
+    Real,
+    Float,
+    Bit,
+    Date,
+    Time,
+    Time2(u16),
+    Timestamp(u16),
+    Binary,
+    WideText,
+    Text,
+}
+
+impl From<&ColumnDescriptor> for Kind {
+    fn from(descriptor: &ColumnDescriptor) -> Self {
+        let digits = descriptor.decimal_digits.unwrap_or(0);
+        match descriptor.data_type {
+            SqlDataType::SQL_INTEGER
+            | SqlDataType::SQL_SMALLINT
+            | SqlDataType::SQL_EXT_TINYINT
+            | SqlDataType::SQL_EXT_BIGINT => Kind::Integer,
+            SqlDataType::SQL_REAL => Kind::Real,
+            SqlDataType::SQL_FLOAT | SqlDataType::SQL_DOUBLE => Kind::Float,
+            SqlDataType::SQL_EXT_BIT => Kind::Bit,
+            SqlDataType::SQL_DATE => Kind::Date,
+            SqlDataType::SQL_TIME => Kind::Time,
+            SqlDataType::SQL_SS_TIME2 => Kind::Time2(digits),
+            SqlDataType::SQL_TIMESTAMP | SqlDataType::SQL_DATETIME => Kind::Timestamp(digits),
+            SqlDataType::SQL_EXT_BINARY
+            | SqlDataType::SQL_EXT_VARBINARY
+            | SqlDataType::SQL_EXT_LONGVARBINARY
+            | SqlDataType::SQL_SS_UDT => Kind::Binary,
+            SqlDataType::SQL_EXT_WCHAR
+            | SqlDataType::SQL_EXT_WVARCHAR
+            | SqlDataType::SQL_EXT_WLONGVARCHAR
+            | SqlDataType::SQL_SS_XML => Kind::WideText,
+            // NUMERIC and DECIMAL are read as text to keep their exact precision
+            _ => Kind::Text,
+        }
+    }
+}
+
+/// NUMERIC and DECIMAL without scale hold integers (Oracle NUMBER(10), for instance)
+impl From<&ColumnDescriptor> for ColumnKind {
+    fn from(descriptor: &ColumnDescriptor) -> Self {
+        match descriptor.data_type {
+            SqlDataType::SQL_NUMERIC | SqlDataType::SQL_DECIMAL
+                if descriptor.decimal_digits == Some(0) =>
+            {
+                ColumnKind::Integer
+            }
+            SqlDataType::SQL_NUMERIC | SqlDataType::SQL_DECIMAL => ColumnKind::Decimal,
+            _ => match Kind::from(descriptor) {
+                Kind::Integer => ColumnKind::Integer,
+                Kind::Real | Kind::Float => ColumnKind::Decimal,
+                Kind::Bit => ColumnKind::Boolean,
+                Kind::Binary => ColumnKind::Binary,
+                Kind::Date => ColumnKind::Date,
+                Kind::Timestamp(_) => ColumnKind::Timestamp,
+                _ => ColumnKind::Text,
+            },
+        }
+    }
+}
+
+/// Reads the value of a column from the current row.
+/// Variable length data (LOBs included) is requested from the driver in chunks,
+/// growing the cursor buffer until SQLGetData reports the whole value was read.
+#[allow(clippy::result_large_err)]
+pub fn read<S>(
+    cursor: &mut Cursor<S, AutocommitOn>,
+    column: u16,
+    kind: Kind,
+) -> odbc::Result<Option<String>> {
+    Ok(match kind {
+        Kind::Integer => cursor.get_data::<i64>(column)?.map(|v| v.to_string()),
+        Kind::Real => cursor.get_data::<f32>(column)?.map(|v| v.to_string()),
+        Kind::Float => cursor.get_data::<f64>(column)?.map(|v| v.to_string()),
+        Kind::Bit => cursor.get_data::<bool>(column)?.map(|v| v.to_string()),
+        Kind::Date => cursor
+            .get_data::<SqlDate>(column)?
+            .map(|v| date_to_string(&v)),
+        Kind::Time => cursor
+            .get_data::<SqlTime>(column)?
+            .map(|v| time_to_string(v.hour, v.minute, v.second, 0, 0)),
+        Kind::Time2(digits) => cursor
+            .get_data::<SqlSsTime2>(column)?
+            .map(|v| time_to_string(v.hour, v.minute, v.second, v.fraction, digits)),
+        Kind::Timestamp(digits) => cursor
+            .get_data::<SqlTimestamp>(column)?
+            .map(|v| timestamp_to_string(&v, digits)),
+        Kind::Binary => cursor.get_data::<Vec<u8>>(column)?.map(|v| hex(&v)),
+        Kind::WideText => cursor
+            .get_data::<Vec<u16>>(column)?
+            .map(|v| String::from_utf16_lossy(&v)),
+        Kind::Text => cursor.get_data::<String>(column)?,
+    })
+}
+
+fn date_to_string(date: &SqlDate) -> String {
+    format!("{:04}-{:02}-{:02}", date.year, date.month, date.day)
+}
+
+/// `fraction` is expressed in nanoseconds
+fn time_to_string(hour: u16, minute: u16, second: u16, fraction: u32, digits: u16) -> String {
+    format!(
+        "{hour:02}:{minute:02}:{second:02}{}",
+        fraction_to_string(fraction, digits)
+    )
+}
+
+fn timestamp_to_string(timestamp: &SqlTimestamp, digits: u16) -> String {
+    format!(
+        "{:04}-{:02}-{:02} {}",
+        timestamp.year,
+        timestamp.month,
+        timestamp.day,
+        time_to_string(
+            timestamp.hour,
+            timestamp.minute,
+            timestamp.second,
+            timestamp.fraction,
+            digits
+        )
+    )
+}
+
+fn fraction_to_string(fraction: u32, digits: u16) -> String {
+    match digits {
+        1..=9 => format!(".{fraction:09}")[..digits as usize + 1].to_string(),
+        _ if fraction > 0 => format!(".{fraction:09}").trim_end_matches('0').to_string(),
+        _ => String::new(),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    format!(
+        "0x{}",
+        bytes.iter().map(|b| format!("{b:02X}")).collect::<String>()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use odbc::{ColumnDescriptor, SqlTimestamp, ffi::SqlDataType};
+
+    use super::{Kind, hex, timestamp_to_string};
+
+    #[test]
+    fn test_kind_from_descriptor() {
+        let descriptor = |data_type, decimal_digits| ColumnDescriptor {
+            name: String::from("COL"),
+            data_type,
+            column_size: None,
+            decimal_digits,
+            nullable: None,
+        };
+
+        assert_eq!(
+            Kind::from(&descriptor(SqlDataType::SQL_DECIMAL, Some(2))),
+            Kind::Text
+        );
+        assert_eq!(
+            Kind::from(&descriptor(SqlDataType::SQL_EXT_WVARCHAR, None)),
+            Kind::WideText
+        );
+        assert_eq!(
+            Kind::from(&descriptor(SqlDataType::SQL_EXT_LONGVARBINARY, None)),
+            Kind::Binary
+        );
+        assert_eq!(
+            Kind::from(&descriptor(SqlDataType::SQL_TIMESTAMP, Some(3))),
+            Kind::Timestamp(3)
+        );
+    }
+
+    #[test]
+    fn test_value_to_string() {
+        let timestamp = SqlTimestamp {
+            year: 2024,
+            month: 5,
+            day: 1,
+            hour: 13,
+            minute: 4,
+            second: 5,
+            fraction: 120_000_000,
+        };
+        assert_eq!(
+            timestamp_to_string(&timestamp, 3),
+            "2024-05-01 13:04:05.120"
+        );
+        assert_eq!(timestamp_to_string(&timestamp, 0), "2024-05-01 13:04:05.12");
+        assert_eq!(hex(&[0xCA, 0xFE]), "0xCAFE");
+    }
+}
@@ -0,0 +1,6 @@
+mod param;
+#[allow(clippy::module_inception)]
+mod postgres;
+mod value;
+
+pub use postgres::Postgres;
@@ -0,0 +1,158 @@
+use std::error::Error;
+
+use bytes::{BufMut, BytesMut};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime};
+use postgres::types::{IsNull, ToSql, Type, to_sql_checked};
+
+use crate::dbeer::params::Param;
+
+type EncodeResult = Result<IsNull, Box<dyn Error + Sync + Send>>;
+
+/// Encodes the `-params` values in Postgres binary format
+/// using the parameter type inferred by the server
+impl ToSql for Param {
+    fn to_sql(&self, ty: &Type, out: &mut BytesMut) -> EncodeResult {
+        let text = match self {
+            Param::Null => return Ok(IsNull::Yes),
+            Param::Bool(v) if *ty == Type::BOOL => return v.to_sql(ty, out),
+            Param::Int(v) => match *ty {
+                Type::INT2 => return i16::try_from(*v)?.to_sql(ty, out),
+                Type::INT4 => return i32::try_from(*v)?.to_sql(ty, out),
+                Type::INT8 => return v.to_sql(ty, out),
+                Type::FLOAT4 => return (*v as f32).to_sql(ty, out),
+                Type::FLOAT8 => return (*v as f64).to_sql(ty, out),
+                _ => v.to_string(),
+            },
+            Param::Float(v) => match *ty {
+                Type::FLOAT4 => return (*v as f32).to_sql(ty, out),
+                Type::FLOAT8 => return v.to_sql(ty, out),
+                _ => v.to_string(),
+            },
+            Param::Bool(v) => v.to_string(),
+            Param::Text(v) => v.clone(),
+        };
+
+        match *ty {
+            Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME | Type::UNKNOWN => {
+                text.as_str().to_sql(ty, out)
+            }
+            Type::BOOL => text.parse::<bool>()?.to_sql(ty, out),
+            Type::INT2 => text.parse::<i16>()?.to_sql(ty, out),
+            Type::INT4 => text.parse::<i32>()?.to_sql(ty, out),
+            Type::INT8 => text.parse::<i64>()?.to_sql(ty, out),
+            Type::FLOAT4 => text.parse::<f32>()?.to_sql(ty, out),
+            Type::FLOAT8 => text.parse::<f64>()?.to_sql(ty, out),
+            Type::NUMERIC => numeric(&text, out),
+            Type::JSON => {
+                out.put_slice(text.as_bytes());
+                Ok(IsNull::No)
+            }
+            Type::JSONB => {
+                out.put_u8(1);
+                out.put_slice(text.as_bytes());
+                Ok(IsNull::No)
+            }
+            Type::UUID => {
+                out.put_slice(uuid::Uuid::parse_str(&text)?.as_bytes());
+                Ok(IsNull::No)
+            }
+            Type::DATE => NaiveDate::parse_from_str(&text, "%Y-%m-%d")?.to_sql(ty, out),
+            Type::TIME => NaiveTime::parse_from_str(&text, "%H:%M:%S%.f")?.to_sql(ty, out),
+            Type::TIMESTAMP => {
+                NaiveDateTime::parse_from_str(&text.replace('T', " "), "%Y-%m-%d %H:%M:%S%.f")?
+                    .to_sql(ty, out)
+            }
+            Type::TIMESTAMPTZ => DateTime::<FixedOffset>::parse_from_str(
+                &text.replace('T', " "),
+                "%Y-%m-%d %H:%M:%S%.f%#z",
+            )?
+            .to_sql(ty, out),
+            _ => Err(format!(
+                "cannot bind {self:?} to a {ty} parameter. Add an explicit cast to the placeholder"
+            )
+            .into()),
+        }
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+
+    to_sql_checked!();
+}
+
+/// Writes a decimal string as NUMERIC: base 10000 digits with weight, sign and scale
+fn numeric(text: &str, out: &mut BytesMut) -> EncodeResult {
+    let text = text.trim();
+    if text.eq_ignore_ascii_case("nan") {
+        out.put_slice(&[0, 0, 0, 0, 0xC0, 0, 0, 0]);
+        return Ok(IsNull::No);
+    }
+
+    let (negative, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text.strip_prefix('+').unwrap_or(text)),
+    };
+    let (integer, fraction) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+    if integer.is_empty() && fraction.is_empty()
+        || !integer
+            .chars()
+            .chain(fraction.chars())
+            .all(|c| c.is_ascii_digit())
+    {
+        return Err(format!("invalid numeric value {text}").into());
+    }
+
+    let integer = integer.trim_start_matches('0');
+    let padded_integer = format!("{}{integer}", "0".repeat((4 - integer.len() % 4) % 4));
+    let padded_fraction = format!("{fraction}{}", "0".repeat((4 - fraction.len() % 4) % 4));
+
+    let mut digits = padded_integer
+        .as_bytes()
+        .chunks(4)
+        .chain(padded_fraction.as_bytes().chunks(4))
+        .map(|chunk| std::str::from_utf8(chunk).unwrap().parse::<i16>().unwrap())
+        .collect::<Vec<_>>();
+    let mut weight = (padded_integer.len() / 4) as i16 - 1;
+
+    while digits.first() == Some(&0) {
+        digits.remove(0);
+        weight -= 1;
+    }
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+    if digits.is_empty() {
+        weight = 0;
+    }
+
+    out.put_i16(digits.len() as i16);
+    out.put_i16(weight);
+    out.put_u16(if negative && !digits.is_empty() {
+        0x4000
+    } else {
+        0
+    });
+    out.put_u16(fraction.len() as u16);
+    for digit in digits {
+        out.put_i16(digit);
+    }
+    Ok(IsNull::No)
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use postgres::types::Type;
+
+    use crate::dbeer::engine::postgres::value::decode;
+
+    #[test]
+    fn test_numeric_round_trip() {
+        for value in ["0", "12.50", "-0.0042", "10000", "123456789.000001"] {
+            let mut out = BytesMut::new();
+            super::numeric(value, &mut out).unwrap();
+            assert_eq!(decode(&Type::NUMERIC, &out, None).unwrap(), value);
+        }
+    }
+}
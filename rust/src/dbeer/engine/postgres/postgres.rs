@@ -0,0 +1,545 @@
+use std::{collections::HashMap, time::Duration};
+
+use chrono_tz::Tz;
+use postgres::{
+    Client, NoTls, Row, SimpleQueryMessage, Statement, Transaction,
+    types::{Oid, ToSql, Type},
+};
+use serde_json::Value;
+
+use crate::{
+    dbeer::{
+        self, Format, Rows, Table,
+        cancel::Canceller,
+        column::ColumnKind,
+        engine::{
+            SqlExecutor, execute_until_failure,
+            postgres::value::{RawValue, has_time_zone, is_supported},
+            single_statement, transaction_outcome,
+        },
+        params::{Param, Params},
+        plan::{Plan, PlanNode},
+        query::{Dialect, is_dml, split_queries},
+        response,
+        secret::redact,
+    },
+    dbeer_debug, dbeer_message,
+};
+
+pub struct Postgres {
+    client: Client,
+    queries: String,
+    params: Params,
+    // A session timeout stays until it is reset
+    timeout_set: bool,
+}
+
+impl Postgres {
+    /// Rows requested from the server at a time
+    const FETCH_SIZE: i32 = 1000;
+
+    #[allow(clippy::result_large_err)]
+    pub fn connect(conn_str: &str, queries: &str, params: Params) -> dbeer::Result<Self> {
+        Ok(Self {
+            queries: queries.to_string(),
+            params,
+            timeout_set: false,
+            client: Client::connect(conn_str, NoTls).map_err(|_| {
+                dbeer::Error::Msg(format!(
+                    "Error connecting Postgres. Connection string: {}",
+                    redact(conn_str)
+                ))
+            })?,
+        })
+    }
+
+    fn sql_params<'a>(values: &[&'a Param]) -> Vec<&'a (dyn ToSql + Sync)> {
+        values
+            .iter()
+            .map(|&value| value as &(dyn ToSql + Sync))
+            .collect()
+    }
+
+    fn column_kind(type_: &Type) -> ColumnKind {
+        match *type_ {
+            Type::INT2 | Type::INT4 | Type::INT8 | Type::OID => ColumnKind::Integer,
+            Type::NUMERIC | Type::FLOAT4 | Type::FLOAT8 => ColumnKind::Decimal,
+            Type::BOOL => ColumnKind::Boolean,
+            Type::BYTEA => ColumnKind::Binary,
+            Type::DATE => ColumnKind::Date,
+            Type::TIMESTAMP => ColumnKind::Timestamp,
+            _ => ColumnKind::Text,
+        }
+    }
+
+    /// Session `TimeZone`, which TIMESTAMPTZ values are shown in.
+    /// `None` when it is not a zone name, e.g. a POSIX zone like `<+03>-03`
+    fn time_zone(client: &mut Client) -> Option<Tz> {
+        let name = match client.query_one("SHOW TimeZone", &[]) {
+            Ok(row) => row.get::<_, String>(0),
+            Err(e) => {
+                dbeer_debug!("Postgres time zone not read: {e}");
+                return None;
+            }
+        };
+        match name.parse() {
+            Ok(zone) => Some(zone),
+            Err(_) => {
+                dbeer_debug!(
+                    "Postgres time zone {name} is not known. The server prints the values"
+                );
+                None
+            }
+        }
+    }
+
+    /// Values the binary decoder fails on are printed by the server
+    #[allow(clippy::result_large_err)]
+    fn row_to_string(
+        transaction: &mut Transaction,
+        row: &Row,
+        zone: Option<Tz>,
+        statements: &mut HashMap<Oid, Statement>,
+    ) -> dbeer::Result<Vec<Option<String>>> {
+        let mut values = Vec::with_capacity(row.len());
+        for (i, column) in row.columns().iter().enumerate() {
+            let value = row
+                .try_get::<_, RawValue>(i)
+                .map_err(dbeer::Error::Postgres)?;
+            values.push(match value.to_string(column.type_(), zone) {
+                Ok(text) => text,
+                Err(e) => {
+                    dbeer_debug!(
+                        "Postgres {} not decoded ({e}). Asking the server",
+                        column.type_()
+                    );
+                    Self::server_text(transaction, column.type_(), &value, statements)?
+                }
+            });
+        }
+        Ok(values)
+    }
+
+    /// Sends the value back to be cast to text, which prints it as text mode would.
+    /// It runs in a savepoint so a failure does not abort the transaction of the portal
+    #[allow(clippy::result_large_err)]
+    fn server_text(
+        transaction: &mut Transaction,
+        type_: &Type,
+        value: &RawValue,
+        statements: &mut HashMap<Oid, Statement>,
+    ) -> dbeer::Result<Option<String>> {
+        let mut savepoint = transaction.transaction().map_err(dbeer::Error::Postgres)?;
+        let statement = match statements.get(&type_.oid()) {
+            Some(statement) => Ok(statement.clone()),
+            None => savepoint.prepare_typed("SELECT $1::text", std::slice::from_ref(type_)),
+        };
+        let text = statement.and_then(|statement| {
+            statements.insert(type_.oid(), statement.clone());
+            savepoint.query_one(&statement, &[value])?.try_get(0)
+        });
+
+        match text {
+            Ok(text) => {
+                savepoint.commit().map_err(dbeer::Error::Postgres)?;
+                Ok(text)
+            }
+            Err(e) => {
+                savepoint.rollback().map_err(dbeer::Error::Postgres)?;
+                Err(dbeer::Error::Msg(format!(
+                    "Postgres type {type_} cannot be shown: {e}. Cast the column to text"
+                )))
+            }
+        }
+    }
+
+    /// Wraps the query so the server casts the columns without a binary decoder to text.
+    /// The rest keep their type and the rows still come from a portal
+    fn text_casts(sql: &str, statement: &Statement) -> String {
+        let columns = statement.columns();
+        let aliases = (0..columns.len())
+            .map(|i| format!("c{i}"))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let select = columns
+            .iter()
+            .enumerate()
+            .map(|(i, column)| {
+                let cast = if is_supported(column.type_()) {
+                    ""
+                } else {
+                    "::text"
+                };
+                format!("c{i}{cast} AS \"{}\"", column.name().replace('"', "\"\""))
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("WITH dbeer_rows ({aliases}) AS ({sql}) SELECT {select} FROM dbeer_rows")
+    }
+
+    /// Runs the query in text mode so the server renders every value itself.
+    /// Used when a column type has no binary decoder and the query cannot be wrapped
+    /// to cast it (SHOW, EXPLAIN, CALL...).
+    /// The simple query protocol has no cursors, so the whole result is received first
+    #[allow(clippy::result_large_err)]
+    fn select_as_text(&mut self, query: &str, rows: &mut dyn Rows) -> dbeer::Result {
+        let mut columns = false;
+
+        for message in self
+            .client
+            .simple_query(query)
+            .map_err(dbeer::Error::Postgres)?
+        {
+            match message {
+                SimpleQueryMessage::RowDescription(description) if !columns => {
+                    let names: Vec<String> =
+                        description.iter().map(|c| c.name().to_string()).collect();
+                    rows.columns(&names)?;
+                    columns = true;
+                }
+                SimpleQueryMessage::Row(row) if !rows.done() => rows.row(
+                    (0..row.len())
+                        .map(|i| row.get(i).map(str::to_string))
+                        .collect(),
+                )?,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Walks a node of `EXPLAIN (FORMAT JSON)` output and its children
+    fn plan_nodes(node: &Value, depth: usize, nodes: &mut Vec<PlanNode>) {
+        let text = |key: &str| node[key].as_str().unwrap_or_default();
+        let number = |key: &str| node[key].as_f64().unwrap_or_default();
+
+        let mut operation = text("Node Type").to_string();
+        if !text("Join Type").is_empty() && operation.ends_with("Join") {
+            operation = format!("{} {operation}", text("Join Type"));
+        }
+        if !text("Index Name").is_empty() {
+            operation.push_str(&format!(" using {}", text("Index Name")));
+        }
+        if !text("Relation Name").is_empty() {
+            operation.push_str(&format!(" on {}", text("Relation Name")));
+        }
+
+        let mut plan_node = PlanNode::new(depth, &operation);
+        plan_node.cost = format!("{:.2}..{:.2}", number("Startup Cost"), number("Total Cost"));
+        plan_node.rows = number("Plan Rows").to_string();
+        if node.get("Actual Total Time").is_some() {
+            plan_node.actual = format!(
+                "time={:.3}..{:.3} rows={} loops={}",
+                number("Actual Startup Time"),
+                number("Actual Total Time"),
+                number("Actual Rows"),
+                number("Actual Loops")
+            );
+        }
+        nodes.push(plan_node);
+
+        for child in node["Plans"].as_array().into_iter().flatten() {
+            Self::plan_nodes(child, depth + 1, nodes);
+        }
+    }
+}
+
+impl SqlExecutor for Postgres {
+    #[allow(clippy::result_large_err)]
+    fn select(&mut self, table: &mut Table) -> dbeer::Result {
+        let query = self.queries.clone();
+        table.show_rows(|rows| self.fetch_rows(&query, rows))
+    }
+
+    /// Rows are fetched from a portal in chunks of `FETCH_SIZE`
+    fn fetch_rows(&mut self, query: &str, rows: &mut dyn Rows) -> dbeer::Result {
+        let (sql, values) = self.params.bind(query, Dialect::Postgres)?;
+        let mut statement = self.client.prepare(&sql).map_err(dbeer::Error::Postgres)?;
+
+        if let Some(type_) = statement
+            .columns()
+            .iter()
+            .map(|c| c.type_().clone())
+            .find(|type_| !is_supported(type_))
+        {
+            match self.client.prepare(&Self::text_casts(&sql, &statement)) {
+                Ok(cast) => {
+                    dbeer_debug!("Postgres type {type_} has no binary decoder. Cast to text");
+                    statement = cast;
+                }
+                // Text mode runs through the simple query protocol which has no parameters
+                Err(e) if !values.is_empty() => {
+                    return Err(dbeer::Error::Msg(format!(
+                        "Postgres type {type_} cannot be shown with bind parameters ({e}). Cast the column to text"
+                    )));
+                }
+                Err(e) => {
+                    dbeer_debug!(
+                        "Postgres type {type_} has no binary decoder and the query cannot be wrapped ({e}). Falling back to text mode"
+                    );
+                    return self.select_as_text(query, rows);
+                }
+            }
+        }
+
+        let names: Vec<String> = statement
+            .columns()
+            .iter()
+            .map(|c| c.name().to_string())
+            .collect();
+        rows.columns(&names)?;
+        rows.column_kinds(
+            &statement
+                .columns()
+                .iter()
+                .map(|c| Self::column_kind(c.type_()))
+                .collect::<Vec<_>>(),
+        );
+
+        let zone = if statement.columns().iter().any(|c| has_time_zone(c.type_())) {
+            Self::time_zone(&mut self.client)
+        } else {
+            None
+        };
+        let mut statements = HashMap::new();
+
+        let mut transaction = self.client.transaction().map_err(dbeer::Error::Postgres)?;
+        let portal = transaction
+            .bind(&statement, &Self::sql_params(&values))
+            .map_err(dbeer::Error::Postgres)?;
+        loop {
+            let chunk = transaction
+                .query_portal(&portal, Self::FETCH_SIZE)
+                .map_err(dbeer::Error::Postgres)?;
+            for row in &chunk {
+                rows.row(Self::row_to_string(
+                    &mut transaction,
+                    row,
+                    zone,
+                    &mut statements,
+                )?)?;
+                if rows.done() {
+                    break;
+                }
+            }
+            if rows.done() || chunk.len() < Self::FETCH_SIZE as usize {
+                break;
+            }
+        }
+
+        // INSERT ... RETURNING runs through here too
+        transaction.commit().map_err(dbeer::Error::Postgres)
+    }
+
+    fn affected_rows(&mut self, query: &str) -> dbeer::Result<u64> {
+        let (sql, values) = self.params.bind(query, Dialect::Postgres)?;
+        self.client
+            .execute(&sql, &Self::sql_params(&values))
+            .map_err(dbeer::Error::Postgres)
+    }
+
+    fn execute(&mut self, table: &mut Table) -> dbeer::Result {
+        let queries = split_queries(&self.queries, Dialect::Postgres);
+        let statements = self.params.bind_all(&queries, Dialect::Postgres)?;
+
+        if queries.len() == 1 {
+            let query = queries[0];
+            let (sql, values) = &statements[0];
+            let result = self
+                .client
+                .execute(sql, &Self::sql_params(values))
+                .map_err(dbeer::Error::Postgres)?;
+
+            if is_dml(query, Dialect::Postgres) {
+                response::affected_rows(result);
+            } else {
+                dbeer_message!("  Statement executed correctly.");
+            }
+            return Ok(());
+        }
+
+        let mut results = Vec::new();
+        for (i, (&query, (sql, values))) in queries.iter().zip(&statements).enumerate() {
+            let msg = match self.client.execute(sql, &Self::sql_params(values)) {
+                Ok(affected) => {
+                    if is_dml(query, Dialect::Postgres) {
+                        format!("{})   Row(s) affected: {}", i + 1, affected)
+                    } else {
+                        format!("{})   Statement executed correctly.", i + 1)
+                    }
+                }
+                Err(e) => format!("{})   {}", i + 1, e),
+            };
+            results.push(msg);
+        }
+
+        table.create_execute_result_file(Format::Standard(results))
+    }
+
+    fn transaction(&mut self, table: &mut Table) -> dbeer::Result {
+        let queries = split_queries(&self.queries, Dialect::Postgres);
+        let statements = self.params.bind_all(&queries, Dialect::Postgres)?;
+        let mut transaction = self.client.transaction().map_err(dbeer::Error::Postgres)?;
+
+        let mut statements = statements.iter();
+        let (mut results, failed) = execute_until_failure(&queries, Dialect::Postgres, |_| {
+            let (sql, values) = statements.next().unwrap();
+            transaction.execute(sql, &Self::sql_params(values))
+        });
+
+        if failed.is_some() {
+            transaction.rollback().map_err(dbeer::Error::Postgres)?;
+        } else {
+            transaction.commit().map_err(dbeer::Error::Postgres)?;
+        }
+        results.push(transaction_outcome(failed));
+
+        table.create_execute_result_file(Format::Standard(results))
+    }
+
+    fn explain(&mut self, table: &mut Table, analyze: bool) -> dbeer::Result {
+        let (statement, values) = self.params.bind(
+            single_statement(&self.queries, Dialect::Postgres)?,
+            Dialect::Postgres,
+        )?;
+        let query = format!(
+            "EXPLAIN (FORMAT JSON{}) {statement}",
+            if analyze { ", ANALYZE" } else { "" },
+        );
+        dbeer_debug!("Explain query: {query}");
+
+        // ANALYZE executes the statement so any change is rolled back
+        let mut transaction = self.client.transaction().map_err(dbeer::Error::Postgres)?;
+        let output = transaction
+            .query(&query, &Self::sql_params(&values))
+            .map_err(dbeer::Error::Postgres)?
+            .first()
+            .and_then(|row| {
+                row.try_get::<_, RawValue>(0)
+                    .ok()
+                    .and_then(|raw| raw.to_string(row.columns()[0].type_(), None).ok())
+                    .flatten()
+            })
+            .unwrap_or_default();
+        transaction.rollback().map_err(dbeer::Error::Postgres)?;
+
+        let json: Value = serde_json::from_str(&output).map_err(dbeer::Error::Serde)?;
+        let mut plan = Plan::default();
+        Self::plan_nodes(&json[0]["Plan"], 0, &mut plan.nodes);
+        plan.render(table)
+    }
+
+    fn tables(&mut self) -> dbeer::Result {
+        let table_names = self
+            .client
+            .query("select table_name from information_schema.tables where table_schema = 'public' order by table_name", &[])
+            .map_err(dbeer::Error::Postgres)?
+            .iter()
+            .map(|row| {
+                let name: String = row.get("table_name");
+                name.to_uppercase()
+            })
+            .collect::<Vec<_>>().join(" ");
+
+        dbeer_debug!("Table names: {table_names}");
+        response::table_names(&table_names);
+
+        Ok(())
+    }
+
+    fn table_info(&mut self, table: &mut Table) -> dbeer::Result {
+        self.queries = self.table_info_query();
+        dbeer_debug!("Table info query: {}", self.queries);
+        self.select(table)
+    }
+
+    fn dialect(&self) -> Dialect {
+        Dialect::Postgres
+    }
+
+    fn set_queries(&mut self, queries: &str, params: Params) {
+        self.queries = queries.to_string();
+        self.params = params;
+    }
+
+    /// Without a timeout the one set by an earlier run is reset,
+    /// so the role or database `statement_timeout` applies again
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> dbeer::Result {
+        let statement = match timeout {
+            Some(timeout) => format!("SET statement_timeout = {}", timeout.as_millis()),
+            None if self.timeout_set => "RESET statement_timeout".to_string(),
+            None => return Ok(()),
+        };
+        self.client
+            .batch_execute(&statement)
+            .map_err(dbeer::Error::Postgres)?;
+        self.timeout_set = timeout.is_some();
+        Ok(())
+    }
+
+    /// Sends a cancel request through a new connection
+    fn canceller(&self) -> Option<Canceller> {
+        let token = self.client.cancel_token();
+        Some(Box::new(move || {
+            if let Err(e) = token.cancel_query(NoTls) {
+                dbeer_debug!("Postgres cancel request failed: {e}");
+            }
+        }))
+    }
+
+    fn table_info_query(&self) -> String {
+        format!(
+            r#"SELECT
+                    UPPER(c.column_name) AS column_name,
+                    c.data_type,
+                    CASE
+                        WHEN c.is_nullable = 'YES' THEN ' '
+                        ELSE ' '
+                    END AS not_null,
+                    CASE
+                        WHEN c.character_maximum_length IS NULL THEN '-'
+                        ELSE CAST(c.character_maximum_length AS TEXT)
+                    END AS length,
+                    string_agg(
+                        CASE
+                            WHEN tc.constraint_type = 'PRIMARY KEY' THEN ' PRIMARY KEY'
+                            WHEN tc.constraint_type = 'FOREIGN KEY' THEN ' FOREIGN KEY'
+                            ELSE ''
+                        END,
+                        ' '
+                    ) AS constraint_type,
+                    string_agg(
+                        CASE
+                            WHEN tc.constraint_type = 'FOREIGN KEY' THEN
+                                ' ' || ccu.table_name || '.' || ccu.column_name
+                            ELSE
+                                ''
+                        END,
+                        ''
+                    ) AS referenced_table_column
+                    FROM
+                        information_schema.columns AS c
+                    LEFT JOIN
+                        information_schema.key_column_usage AS kcu
+                        ON c.table_name = kcu.table_name
+                        AND c.column_name = kcu.column_name
+                    LEFT JOIN
+                        information_schema.table_constraints AS tc
+                        ON kcu.constraint_name = tc.constraint_name
+                        AND kcu.table_schema = tc.table_schema
+                    LEFT JOIN
+                        information_schema.referential_constraints AS rc
+                        ON tc.constraint_name = rc.constraint_name
+                    LEFT JOIN
+                        information_schema.key_column_usage AS ccu
+                        ON rc.unique_constraint_name = ccu.constraint_name
+                    WHERE
+                        c.table_name = '{}'
+                    GROUP BY
+                        c.data_type, c.column_name, c.is_nullable, c.character_maximum_length
+                    ORDER BY
+                        constraint_type desc;"#,
+            self.queries
+        )
+    }
+}
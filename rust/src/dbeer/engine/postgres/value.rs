@@ -0,0 +1,909 @@
+use std::{
+    error::Error,
+    fmt::{Display, LowerExp},
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+use bytes::BytesMut;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Timelike};
+use chrono_tz::Tz;
+use postgres::types::{FromSql, IsNull, Kind, ToSql, Type, to_sql_checked};
+
+type DecodeResult<T = String> = Result<T, Box<dyn Error + Sync + Send>>;
+
+/// Raw column value in Postgres binary format, decoded lazily by [`decode`]
+#[derive(Debug)]
+pub struct RawValue<'a>(Option<&'a [u8]>);
+
+impl<'a> FromSql<'a> for RawValue<'a> {
+    fn from_sql(_: &Type, raw: &'a [u8]) -> DecodeResult<Self> {
+        Ok(RawValue(Some(raw)))
+    }
+
+    fn from_sql_null(_: &Type) -> DecodeResult<Self> {
+        Ok(RawValue(None))
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+}
+
+/// Sends the value back as it was received, for the server to print it
+impl ToSql for RawValue<'_> {
+    fn to_sql(&self, _: &Type, out: &mut BytesMut) -> DecodeResult<IsNull> {
+        match self.0 {
+            Some(raw) => {
+                out.extend_from_slice(raw);
+                Ok(IsNull::No)
+            }
+            None => Ok(IsNull::Yes),
+        }
+    }
+
+    fn accepts(_: &Type) -> bool {
+        true
+    }
+
+    to_sql_checked!();
+}
+
+impl RawValue<'_> {
+    /// Decodes the value to its display form. `Ok(None)` means SQL NULL
+    pub fn to_string(&self, ty: &Type, zone: Option<Tz>) -> DecodeResult<Option<String>> {
+        self.0.map(|raw| decode(ty, raw, zone)).transpose()
+    }
+}
+
+/// Returns true if values of this type are shown in the session time zone
+pub fn has_time_zone(ty: &Type) -> bool {
+    match ty.kind() {
+        Kind::Array(inner) | Kind::Range(inner) | Kind::Multirange(inner) | Kind::Domain(inner) => {
+            has_time_zone(inner)
+        }
+        Kind::Composite(fields) => fields.iter().any(|field| has_time_zone(field.type_())),
+        _ => *ty == Type::TIMESTAMPTZ,
+    }
+}
+
+/// Returns true if every value of this type can be decoded from its binary format
+pub fn is_supported(ty: &Type) -> bool {
+    match ty.kind() {
+        Kind::Enum(_) => true,
+        Kind::Array(inner) | Kind::Range(inner) | Kind::Multirange(inner) | Kind::Domain(inner) => {
+            is_supported(inner)
+        }
+        Kind::Composite(fields) => fields.iter().all(|field| is_supported(field.type_())),
+        _ => is_supported_simple(ty),
+    }
+}
+
+fn is_supported_simple(ty: &Type) -> bool {
+    matches!(
+        *ty,
+        Type::BOOL
+            | Type::CHAR
+            | Type::INT2
+            | Type::INT4
+            | Type::INT8
+            | Type::OID
+            | Type::XID
+            | Type::CID
+            | Type::FLOAT4
+            | Type::FLOAT8
+            | Type::NUMERIC
+            | Type::MONEY
+            | Type::TEXT
+            | Type::VARCHAR
+            | Type::BPCHAR
+            | Type::NAME
+            | Type::UNKNOWN
+            | Type::JSON
+            | Type::JSONB
+            | Type::XML
+            | Type::BYTEA
+            | Type::DATE
+            | Type::TIME
+            | Type::TIMETZ
+            | Type::TIMESTAMP
+            | Type::TIMESTAMPTZ
+            | Type::INTERVAL
+            | Type::UUID
+            | Type::INET
+            | Type::CIDR
+            | Type::MACADDR
+            | Type::MACADDR8
+            | Type::BIT
+            | Type::VARBIT
+            | Type::POINT
+            | Type::LSEG
+            | Type::LINE
+            | Type::BOX
+            | Type::PATH
+            | Type::POLYGON
+            | Type::CIRCLE
+            | Type::PG_LSN
+    ) || matches!(ty.name(), "citext" | "ltree" | "lquery")
+}
+
+/// Decodes a binary Postgres value into the same text the server would print.
+/// TIMESTAMPTZ values are shown in `zone`, the session `TimeZone`. They fail when it is unknown
+pub fn decode(ty: &Type, raw: &[u8], zone: Option<Tz>) -> DecodeResult {
+    match ty.kind() {
+        Kind::Enum(_) => return text(raw),
+        Kind::Domain(inner) => return decode(inner, raw, zone),
+        Kind::Array(inner) => return array(inner, raw, zone),
+        Kind::Range(inner) => return range(inner, &mut Reader(raw), zone),
+        Kind::Multirange(inner) => return multirange(inner, raw, zone),
+        Kind::Composite(fields) => {
+            let types = fields.iter().map(|f| f.type_().clone()).collect::<Vec<_>>();
+            return composite(&types, raw, zone);
+        }
+        _ => {}
+    }
+
+    let mut reader = Reader(raw);
+    let value = match *ty {
+        Type::BOOL => (reader.u8()? != 0).to_string(),
+        Type::CHAR => char::from(reader.u8()?).to_string(),
+        Type::INT2 => reader.i16()?.to_string(),
+        Type::INT4 => reader.i32()?.to_string(),
+        Type::INT8 => reader.i64()?.to_string(),
+        Type::OID | Type::XID | Type::CID => reader.u32()?.to_string(),
+        Type::FLOAT4 => shortest(reader.f32()?, f32::DIGITS as i32),
+        Type::FLOAT8 => float(reader.f64()?),
+        Type::NUMERIC => numeric(&mut reader)?,
+        Type::MONEY => money(reader.i64()?),
+        Type::JSONB => {
+            let version = reader.u8()?;
+            if version != 1 {
+                return Err(format!("unsupported JSONB version {version}").into());
+            }
+            text(reader.0)?
+        }
+        Type::BYTEA => bytea_to_string(raw),
+        Type::DATE => date(reader.i32()?)?,
+        Type::TIME => time(reader.i64()?),
+        Type::TIMETZ => {
+            let micros = reader.i64()?;
+            let offset = -reader.i32()?;
+            format!("{}{}", time(micros), utc_offset(offset))
+        }
+        Type::TIMESTAMP => timestamp(reader.i64()?)?,
+        Type::TIMESTAMPTZ => {
+            timestamp_tz(reader.i64()?, zone.ok_or("session time zone is unknown")?)?
+        }
+        Type::INTERVAL => {
+            let micros = reader.i64()?;
+            let days = reader.i32()?;
+            let months = reader.i32()?;
+            interval(months, days, micros)
+        }
+        Type::UUID => uuid::Uuid::from_slice(raw)?.to_string(),
+        Type::INET | Type::CIDR => inet(&mut reader, *ty == Type::CIDR)?,
+        Type::MACADDR | Type::MACADDR8 => raw
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<Vec<_>>()
+            .join(":"),
+        Type::BIT | Type::VARBIT => {
+            let len = reader.i32()? as usize;
+            (0..len)
+                .map(|i| {
+                    let byte = reader.0.get(i / 8).copied().unwrap_or_default();
+                    if byte & (0x80 >> (i % 8)) != 0 {
+                        '1'
+                    } else {
+                        '0'
+                    }
+                })
+                .collect()
+        }
+        Type::POINT => point(&mut reader)?,
+        Type::LSEG => format!("[{},{}]", point(&mut reader)?, point(&mut reader)?),
+        Type::BOX => format!("{},{}", point(&mut reader)?, point(&mut reader)?),
+        Type::LINE => format!(
+            "{{{},{},{}}}",
+            float(reader.f64()?),
+            float(reader.f64()?),
+            float(reader.f64()?)
+        ),
+        Type::PATH => {
+            let closed = reader.u8()? != 0;
+            let points = points(&mut reader)?;
+            if closed {
+                format!("({points})")
+            } else {
+                format!("[{points}]")
+            }
+        }
+        Type::POLYGON => format!("({})", points(&mut reader)?),
+        Type::CIRCLE => format!("<{},{}>", point(&mut reader)?, float(reader.f64()?)),
+        Type::PG_LSN => {
+            let lsn = reader.u64()?;
+            format!("{:X}/{:X}", lsn >> 32, lsn & 0xFFFF_FFFF)
+        }
+        _ => text(raw)?,
+    };
+
+    Ok(value)
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> DecodeResult<&'a [u8]> {
+        if self.0.len() < len {
+            return Err("unexpected end of value".into());
+        }
+        let (head, tail) = self.0.split_at(len);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> DecodeResult<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn i16(&mut self) -> DecodeResult<i16> {
+        Ok(i16::from_be_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn u16(&mut self) -> DecodeResult<u16> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into()?))
+    }
+
+    fn i32(&mut self) -> DecodeResult<i32> {
+        Ok(i32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u32(&mut self) -> DecodeResult<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn i64(&mut self) -> DecodeResult<i64> {
+        Ok(i64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> DecodeResult<u64> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn f32(&mut self) -> DecodeResult<f32> {
+        Ok(f32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn f64(&mut self) -> DecodeResult<f64> {
+        Ok(f64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+
+    /// Length-prefixed value where -1 means NULL
+    fn value(&mut self) -> DecodeResult<Option<&'a [u8]>> {
+        let len = self.i32()?;
+        if len < 0 {
+            Ok(None)
+        } else {
+            Ok(Some(self.take(len as usize)?))
+        }
+    }
+}
+
+fn text(raw: &[u8]) -> DecodeResult {
+    Ok(std::str::from_utf8(raw)?.to_string())
+}
+
+pub fn bytea_to_string(raw: &[u8]) -> String {
+    let hex = raw.iter().map(|b| format!("{b:02x}")).collect::<String>();
+    format!("\\x{hex}")
+}
+
+fn float(value: f64) -> String {
+    shortest(value, f64::DIGITS as i32)
+}
+
+/// Shortest digits reading back as the same value, like the server's default
+/// `extra_float_digits`. Exponents below -4 or from `digits` on use exponent notation, e.g. `1e+20`
+fn shortest<F: Display + LowerExp + Into<f64> + Copy>(value: F, digits: i32) -> String {
+    let as_f64: f64 = value.into();
+    if as_f64.is_infinite() {
+        return if as_f64 > 0.0 {
+            "Infinity"
+        } else {
+            "-Infinity"
+        }
+        .to_string();
+    }
+
+    let scientific = format!("{value:e}");
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent = exponent.parse::<i32>().unwrap_or_default();
+    if as_f64 == 0.0 || as_f64.is_nan() || (-4..digits).contains(&exponent) {
+        value.to_string()
+    } else {
+        let sign = if exponent < 0 { '-' } else { '+' };
+        format!("{mantissa}e{sign}{:02}", exponent.abs())
+    }
+}
+
+fn numeric(reader: &mut Reader) -> DecodeResult {
+    const NUMERIC_NEG: u16 = 0x4000;
+    const NUMERIC_NAN: u16 = 0xC000;
+    const NUMERIC_PINF: u16 = 0xD000;
+    const NUMERIC_NINF: u16 = 0xF000;
+
+    let ndigits = reader.i16()? as i32;
+    let weight = reader.i16()? as i32;
+    let sign = reader.u16()?;
+    let dscale = reader.u16()? as usize;
+
+    match sign {
+        NUMERIC_NAN => return Ok("NaN".to_string()),
+        NUMERIC_PINF => return Ok("Infinity".to_string()),
+        NUMERIC_NINF => return Ok("-Infinity".to_string()),
+        _ => {}
+    }
+
+    let digits = (0..ndigits)
+        .map(|_| reader.i16())
+        .collect::<DecodeResult<Vec<_>>>()?;
+    let digit = |i: i32| {
+        if i >= 0 && i < ndigits {
+            digits[i as usize]
+        } else {
+            0
+        }
+    };
+
+    let mut result = String::new();
+    if sign == NUMERIC_NEG {
+        result.push('-');
+    }
+
+    if weight < 0 {
+        result.push('0');
+    } else {
+        for i in 0..=weight {
+            if i == 0 {
+                result.push_str(&digit(i).to_string());
+            } else {
+                result.push_str(&format!("{:04}", digit(i)));
+            }
+        }
+    }
+
+    if dscale > 0 {
+        let mut fraction = String::new();
+        let mut i = weight + 1;
+        while fraction.len() < dscale {
+            fraction.push_str(&format!("{:04}", digit(i)));
+            i += 1;
+        }
+        fraction.truncate(dscale);
+        result.push('.');
+        result.push_str(&fraction);
+    }
+
+    Ok(result)
+}
+
+fn money(cents: i64) -> String {
+    let sign = if cents < 0 { "-" } else { "" };
+    let cents = cents.unsigned_abs();
+    let units = (cents / 100).to_string();
+
+    let mut grouped = String::new();
+    for (i, c) in units.chars().enumerate() {
+        if i > 0 && (units.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+
+    format!("{sign}${grouped}.{:02}", cents % 100)
+}
+
+fn pg_epoch() -> NaiveDateTime {
+    NaiveDate::from_ymd_opt(2000, 1, 1)
+        .unwrap()
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+}
+
+/// Instant `micros` after the Postgres epoch. Postgres goes further than chrono
+/// (TIMESTAMP to 294276 AD), so values out of its range are left to the server
+fn after_epoch(micros: i64) -> DecodeResult<NaiveDateTime> {
+    pg_epoch()
+        .checked_add_signed(Duration::microseconds(micros))
+        .ok_or_else(|| "timestamp out of range".into())
+}
+
+/// Date of the ISO `DateStyle` and its era, e.g. `0044-03-15` and ` BC`
+fn iso_date(date: NaiveDate) -> (String, &'static str) {
+    let (year, era) = match date.year() {
+        year if year <= 0 => (1 - year, " BC"),
+        year => (year, ""),
+    };
+    (
+        format!("{year:04}-{:02}-{:02}", date.month(), date.day()),
+        era,
+    )
+}
+
+/// Seconds keep their fraction without trailing zeros, e.g. `10:15:00.5`
+fn iso_time(time: NaiveTime) -> String {
+    let text = format!(
+        "{:02}:{:02}:{:02}",
+        time.hour(),
+        time.minute(),
+        time.second()
+    );
+    match time.nanosecond() / 1000 {
+        0 => text,
+        micros => format!("{text}.{}", format!("{micros:06}").trim_end_matches('0')),
+    }
+}
+
+fn date(days: i32) -> DecodeResult {
+    Ok(match days {
+        i32::MAX => "infinity".to_string(),
+        i32::MIN => "-infinity".to_string(),
+        days => {
+            let date = pg_epoch()
+                .date()
+                .checked_add_signed(Duration::days(days as i64))
+                .ok_or("date out of range")?;
+            let (date, era) = iso_date(date);
+            format!("{date}{era}")
+        }
+    })
+}
+
+fn time(micros: i64) -> String {
+    // The end of the day is a valid TIME
+    const DAY: i64 = 86_400_000_000;
+    if micros == DAY {
+        return "24:00:00".to_string();
+    }
+    iso_time(NaiveTime::MIN + Duration::microseconds(micros))
+}
+
+fn timestamp(micros: i64) -> DecodeResult {
+    Ok(match micros {
+        i64::MAX => "infinity".to_string(),
+        i64::MIN => "-infinity".to_string(),
+        micros => {
+            let datetime = after_epoch(micros)?;
+            let (date, era) = iso_date(datetime.date());
+            format!("{date} {}{era}", iso_time(datetime.time()))
+        }
+    })
+}
+
+/// Local time in the zone followed by its offset at that instant, e.g. `2025-06-03 10:15:00-03`
+fn timestamp_tz(micros: i64, zone: Tz) -> DecodeResult {
+    Ok(match micros {
+        i64::MAX => "infinity".to_string(),
+        i64::MIN => "-infinity".to_string(),
+        micros => {
+            let utc = after_epoch(micros)?;
+            let offset = zone.offset_from_utc_datetime(&utc).fix().local_minus_utc();
+            let local = utc
+                .checked_add_signed(Duration::seconds(offset as i64))
+                .ok_or("timestamp out of range")?;
+            let (date, era) = iso_date(local.date());
+            format!(
+                "{date} {}{}{era}",
+                iso_time(local.time()),
+                utc_offset(offset)
+            )
+        }
+    })
+}
+
+fn utc_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+    let (hours, minutes, secs) = (seconds / 3600, (seconds % 3600) / 60, seconds % 60);
+
+    match (minutes, secs) {
+        (0, 0) => format!("{sign}{hours:02}"),
+        (_, 0) => format!("{sign}{hours:02}:{minutes:02}"),
+        _ => format!("{sign}{hours:02}:{minutes:02}:{secs:02}"),
+    }
+}
+
+/// Same layout as the server's default `IntervalStyle` (postgres)
+pub fn interval(months: i32, days: i32, micros: i64) -> String {
+    let plural =
+        |n: i64, unit: &str, units: &str| format!("{n} {}", if n == 1 { unit } else { units });
+
+    let mut parts = Vec::new();
+    let (years, months) = (months / 12, months % 12);
+    if years != 0 {
+        parts.push(plural(years as i64, "year", "years"));
+    }
+    if months != 0 {
+        parts.push(plural(months as i64, "mon", "mons"));
+    }
+    if days != 0 {
+        parts.push(plural(days as i64, "day", "days"));
+    }
+
+    if micros != 0 || parts.is_empty() {
+        let sign = if micros < 0 { "-" } else { "" };
+        let micros = micros.unsigned_abs();
+        let secs = micros / 1_000_000;
+        let fraction = micros % 1_000_000;
+
+        let mut time = format!(
+            "{sign}{:02}:{:02}:{:02}",
+            secs / 3600,
+            (secs % 3600) / 60,
+            secs % 60
+        );
+        if fraction != 0 {
+            time.push_str(format!(".{fraction:06}").trim_end_matches('0'));
+        }
+        parts.push(time);
+    }
+
+    parts.join(" ")
+}
+
+fn inet(reader: &mut Reader, is_cidr: bool) -> DecodeResult {
+    const PGSQL_AF_INET: u8 = 2;
+
+    let family = reader.u8()?;
+    let bits = reader.u8()?;
+    let _is_cidr = reader.u8()?;
+    let len = reader.u8()? as usize;
+    let addr = reader.take(len)?;
+
+    let (addr, max_bits) = if family == PGSQL_AF_INET {
+        let octets: [u8; 4] = addr.try_into()?;
+        (Ipv4Addr::from(octets).to_string(), 32)
+    } else {
+        let octets: [u8; 16] = addr.try_into()?;
+        (Ipv6Addr::from(octets).to_string(), 128)
+    };
+
+    if is_cidr || bits != max_bits {
+        Ok(format!("{addr}/{bits}"))
+    } else {
+        Ok(addr)
+    }
+}
+
+fn point(reader: &mut Reader) -> DecodeResult {
+    Ok(format!(
+        "({},{})",
+        float(reader.f64()?),
+        float(reader.f64()?)
+    ))
+}
+
+fn points(reader: &mut Reader) -> DecodeResult {
+    let len = reader.i32()?;
+    Ok((0..len)
+        .map(|_| point(reader))
+        .collect::<DecodeResult<Vec<_>>>()?
+        .join(","))
+}
+
+/// Quotes an element of an array or composite the way the server does
+fn quote_element(value: &str, special: &[char]) -> String {
+    let needs_quotes = value.is_empty()
+        || value.eq_ignore_ascii_case("null")
+        || value
+            .chars()
+            .any(|c| c.is_whitespace() || c == '"' || c == '\\' || special.contains(&c));
+
+    if needs_quotes {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn array(element_type: &Type, raw: &[u8], zone: Option<Tz>) -> DecodeResult {
+    let mut reader = Reader(raw);
+    let dimensions = reader.i32()?;
+    let _has_nulls = reader.i32()?;
+    let _element_oid = reader.u32()?;
+
+    if dimensions <= 0 {
+        return Ok("{}".to_string());
+    }
+
+    let lengths = (0..dimensions)
+        .map(|_| {
+            let len = reader.i32()?;
+            let _lower_bound = reader.i32()?;
+            Ok(len as usize)
+        })
+        .collect::<DecodeResult<Vec<_>>>()?;
+
+    let total = lengths.iter().product::<usize>();
+    let elements = (0..total)
+        .map(|_| {
+            Ok(match reader.value()? {
+                Some(value) => quote_element(&decode(element_type, value, zone)?, &[',', '{', '}']),
+                None => "NULL".to_string(),
+            })
+        })
+        .collect::<DecodeResult<Vec<_>>>()?;
+
+    fn nest(elements: &[String], lengths: &[usize]) -> String {
+        if lengths.len() == 1 {
+            return format!("{{{}}}", elements.join(","));
+        }
+
+        let chunk = elements.len() / lengths[0].max(1);
+        let inner = elements
+            .chunks(chunk.max(1))
+            .map(|chunk| nest(chunk, &lengths[1..]))
+            .collect::<Vec<_>>();
+        format!("{{{}}}", inner.join(","))
+    }
+
+    Ok(nest(&elements, &lengths))
+}
+
+fn range(element_type: &Type, reader: &mut Reader, zone: Option<Tz>) -> DecodeResult {
+    const RANGE_EMPTY: u8 = 0x01;
+    const RANGE_LB_INC: u8 = 0x02;
+    const RANGE_UB_INC: u8 = 0x04;
+    const RANGE_LB_INF: u8 = 0x08;
+    const RANGE_UB_INF: u8 = 0x10;
+
+    let flags = reader.u8()?;
+    if flags & RANGE_EMPTY != 0 {
+        return Ok("empty".to_string());
+    }
+
+    let mut bound = |infinite: bool| -> DecodeResult {
+        if infinite {
+            return Ok(String::new());
+        }
+        let value = reader.value()?.ok_or("range bound can not be NULL")?;
+        Ok(quote_element(
+            &decode(element_type, value, zone)?,
+            &[',', '(', ')', '[', ']'],
+        ))
+    };
+
+    let lower = bound(flags & RANGE_LB_INF != 0)?;
+    let upper = bound(flags & RANGE_UB_INF != 0)?;
+
+    Ok(format!(
+        "{}{lower},{upper}{}",
+        if flags & RANGE_LB_INC != 0 { '[' } else { '(' },
+        if flags & RANGE_UB_INC != 0 { ']' } else { ')' }
+    ))
+}
+
+fn multirange(element_type: &Type, raw: &[u8], zone: Option<Tz>) -> DecodeResult {
+    let mut reader = Reader(raw);
+    let len = reader.i32()?;
+    let ranges = (0..len)
+        .map(|_| {
+            let value = reader.value()?.ok_or("range can not be NULL")?;
+            range(element_type, &mut Reader(value), zone)
+        })
+        .collect::<DecodeResult<Vec<_>>>()?;
+
+    Ok(format!("{{{}}}", ranges.join(",")))
+}
+
+fn composite(types: &[Type], raw: &[u8], zone: Option<Tz>) -> DecodeResult {
+    let mut reader = Reader(raw);
+    let len = reader.i32()?;
+    let fields = (0..len as usize)
+        .map(|i| {
+            let _oid = reader.u32()?;
+            Ok(match (reader.value()?, types.get(i)) {
+                (Some(value), Some(ty)) => {
+                    quote_element(&decode(ty, value, zone)?, &[',', '(', ')'])
+                }
+                (Some(value), None) => quote_element(&text(value)?, &[',', '(', ')']),
+                (None, _) => String::new(),
+            })
+        })
+        .collect::<DecodeResult<Vec<_>>>()?;
+
+    Ok(format!("({})", fields.join(",")))
+}
+
+#[cfg(test)]
+mod tests {
+    use postgres::types::Type;
+
+    use chrono::NaiveDate;
+    use chrono_tz::Tz;
+
+    use crate::dbeer::engine::postgres::value::{decode, interval, pg_epoch};
+
+    fn numeric(ndigits: i16, weight: i16, sign: u16, dscale: u16, digits: &[i16]) -> Vec<u8> {
+        let mut raw = Vec::new();
+        raw.extend(ndigits.to_be_bytes());
+        raw.extend(weight.to_be_bytes());
+        raw.extend(sign.to_be_bytes());
+        raw.extend(dscale.to_be_bytes());
+        for d in digits {
+            raw.extend(d.to_be_bytes());
+        }
+        raw
+    }
+
+    #[test]
+    fn test_numeric() {
+        let raw = numeric(3, 1, 0, 2, &[12, 3456, 7800]);
+        assert_eq!(decode(&Type::NUMERIC, &raw, None).unwrap(), "123456.78");
+
+        let raw = numeric(1, -1, 0x4000, 5, &[50]);
+        assert_eq!(decode(&Type::NUMERIC, &raw, None).unwrap(), "-0.00500");
+
+        let raw = numeric(0, 0, 0xC000, 0, &[]);
+        assert_eq!(decode(&Type::NUMERIC, &raw, None).unwrap(), "NaN");
+    }
+
+    #[test]
+    fn test_interval() {
+        assert_eq!(
+            interval(14, 3, 3_723_500_000),
+            "1 year 2 mons 3 days 01:02:03.5"
+        );
+        assert_eq!(interval(0, 0, 0), "00:00:00");
+        assert_eq!(interval(0, -1, 0), "-1 days");
+    }
+
+    #[test]
+    fn test_array() {
+        let mut raw = Vec::new();
+        raw.extend(1i32.to_be_bytes());
+        raw.extend(1i32.to_be_bytes());
+        raw.extend(25u32.to_be_bytes());
+        raw.extend(3i32.to_be_bytes());
+        raw.extend(1i32.to_be_bytes());
+        for value in ["a", "b c"] {
+            raw.extend((value.len() as i32).to_be_bytes());
+            raw.extend(value.as_bytes());
+        }
+        raw.extend((-1i32).to_be_bytes());
+
+        assert_eq!(
+            decode(&Type::TEXT_ARRAY, &raw, None).unwrap(),
+            r#"{a,"b c",NULL}"#
+        );
+    }
+
+    #[test]
+    fn test_range_and_misc() {
+        let mut raw = vec![0x02];
+        raw.extend(4i32.to_be_bytes());
+        raw.extend(1i32.to_be_bytes());
+        raw.extend(4i32.to_be_bytes());
+        raw.extend(10i32.to_be_bytes());
+        assert_eq!(decode(&Type::INT4_RANGE, &raw, None).unwrap(), "[1,10)");
+
+        assert_eq!(
+            decode(&Type::MONEY, &123456789i64.to_be_bytes(), None).unwrap(),
+            "$1,234,567.89"
+        );
+        assert_eq!(
+            decode(&Type::BYTEA, &[0xde, 0xad], None).unwrap(),
+            "\\xdead"
+        );
+        assert_eq!(
+            decode(&Type::INET, &[2, 24, 0, 4, 10, 0, 0, 0], None).unwrap(),
+            "10.0.0.0/24"
+        );
+    }
+
+    #[test]
+    fn test_timestamp_tz() {
+        let utc = NaiveDate::from_ymd_opt(2024, 5, 1)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap();
+        let raw = (utc - pg_epoch()).num_microseconds().unwrap().to_be_bytes();
+
+        assert_eq!(
+            decode(
+                &Type::TIMESTAMPTZ,
+                &raw,
+                Some(Tz::America__Argentina__Buenos_Aires)
+            )
+            .unwrap(),
+            "2024-05-01 07:00:00-03"
+        );
+        assert_eq!(
+            decode(&Type::TIMESTAMPTZ, &raw, Some(Tz::Asia__Kolkata)).unwrap(),
+            "2024-05-01 15:30:00+05:30"
+        );
+        assert!(decode(&Type::TIMESTAMPTZ, &raw, None).is_err());
+    }
+
+    #[test]
+    fn test_date_and_time_bounds() {
+        // 4714-11-24 BC, the lowest DATE and TIMESTAMP
+        let first_day = -2_451_545i32;
+        let first_micros = first_day as i64 * 86_400_000_000;
+        assert_eq!(
+            decode(&Type::DATE, &first_day.to_be_bytes(), None).unwrap(),
+            "4714-11-24 BC"
+        );
+        assert_eq!(
+            decode(&Type::TIMESTAMP, &first_micros.to_be_bytes(), None).unwrap(),
+            "4714-11-24 00:00:00 BC"
+        );
+        assert_eq!(
+            decode(
+                &Type::TIMESTAMPTZ,
+                &first_micros.to_be_bytes(),
+                Some(Tz::UTC)
+            )
+            .unwrap(),
+            "4714-11-24 00:00:00+00 BC"
+        );
+
+        // 5874897-12-31 and 294276-12-31 23:59:59.999999 are past chrono, so the server prints them
+        let last_day = 2_145_031_948i32;
+        let last_micros = 9_223_371_331_199_999_999i64;
+        assert!(decode(&Type::DATE, &last_day.to_be_bytes(), None).is_err());
+        assert!(decode(&Type::TIMESTAMP, &last_micros.to_be_bytes(), None).is_err());
+        assert!(
+            decode(
+                &Type::TIMESTAMPTZ,
+                &last_micros.to_be_bytes(),
+                Some(Tz::UTC)
+            )
+            .is_err()
+        );
+
+        assert_eq!(
+            decode(&Type::DATE, &i32::MAX.to_be_bytes(), None).unwrap(),
+            "infinity"
+        );
+        assert_eq!(
+            decode(&Type::TIME, &86_400_000_000i64.to_be_bytes(), None).unwrap(),
+            "24:00:00"
+        );
+        assert_eq!(
+            decode(&Type::TIME, &37_800_500_000i64.to_be_bytes(), None).unwrap(),
+            "10:30:00.5"
+        );
+    }
+
+    #[test]
+    fn test_float() {
+        assert_eq!(
+            decode(&Type::FLOAT8, &1e20f64.to_be_bytes(), None).unwrap(),
+            "1e+20"
+        );
+        assert_eq!(
+            decode(&Type::FLOAT8, &1.5e-5f64.to_be_bytes(), None).unwrap(),
+            "1.5e-05"
+        );
+        assert_eq!(
+            decode(&Type::FLOAT8, &0.0001f64.to_be_bytes(), None).unwrap(),
+            "0.0001"
+        );
+        assert_eq!(
+            decode(&Type::FLOAT8, &123456789012345f64.to_be_bytes(), None).unwrap(),
+            "123456789012345"
+        );
+        assert_eq!(
+            decode(&Type::FLOAT4, &0.1f32.to_be_bytes(), None).unwrap(),
+            "0.1"
+        );
+        assert_eq!(
+            decode(&Type::FLOAT4, &1234567f32.to_be_bytes(), None).unwrap(),
+            "1.234567e+06"
+        );
+        assert_eq!(
+            decode(&Type::FLOAT8, &f64::NEG_INFINITY.to_be_bytes(), None).unwrap(),
+            "-Infinity"
+        );
+    }
+}
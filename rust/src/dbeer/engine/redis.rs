@@ -1,6 +1,11 @@
+use std::time::Duration;
+
 use redis::{Client, Commands, Connection};
 
-use crate::dbeer;
+use crate::{
+    dbeer::{self, secret::redact},
+    dbeer_message,
+};
 
 pub struct Redis {
     queries: String,
@@ -15,12 +20,25 @@ impl Redis {
             queries: queries.to_string(),
             connection: client.get_connection().map_err(|_| {
                 dbeer::Error::Msg(format!(
-                    "Error connecting Redis. Connection string: {conn_str}"
+                    "Error connecting Redis. Connection string: {}",
+                    redact(conn_str)
                 ))
             })?,
         })
     }
 
+    pub fn set_queries(&mut self, queries: &str) {
+        self.queries = queries.to_string();
+    }
+
+    /// Socket timeouts, Redis has no server side limit
+    #[allow(clippy::result_large_err)]
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) -> dbeer::Result {
+        self.connection.set_read_timeout(timeout)?;
+        self.connection.set_write_timeout(timeout)?;
+        Ok(())
+    }
+
     #[allow(clippy::result_large_err)]
     pub fn run(&mut self) -> dbeer::Result {
         Command::convert(&self.queries)?.execute(&mut self.connection)
@@ -53,27 +71,27 @@ impl<'a> Command<'a> {
         match self {
             Self::Get(key) => {
                 let result: Option<String> = connection.get(key)?;
-                println!("Key '{key}' is '{}'", result.unwrap_or("nil".to_string()));
+                dbeer_message!("Key '{key}' is '{}'", result.unwrap_or("nil".to_string()));
                 Ok(())
             }
             Self::Del(keys) => {
                 let result: i32 = connection.del(keys)?;
-                println!("{result} key(s) have been deleted.");
+                dbeer_message!("{result} key(s) have been deleted.");
                 Ok(())
             }
             Self::Expire { key, seconds } => {
                 let _: () = connection.expire(key, *seconds)?;
-                println!("Key '{key}' has been set with expiration of {seconds}s");
+                dbeer_message!("Key '{key}' has been set with expiration of {seconds}s");
                 Ok(())
             }
             Self::Set { key, value } => {
                 let _: () = connection.set(key, value)?;
-                println!("  Key '{key}' has been set with '{value}'");
+                dbeer_message!("  Key '{key}' has been set with '{value}'");
                 Ok(())
             }
             Self::Exists(key) => {
                 let result: bool = connection.exists(key)?;
-                println!(
+                dbeer_message!(
                     "Key '{key}' {}",
                     if result { "exists." } else { "does not exist." }
                 );
@@ -81,12 +99,12 @@ impl<'a> Command<'a> {
             }
             Self::Keys(key) => {
                 let result: Vec<String> = connection.keys(key)?;
-                println!("Pattern '{key}' returns: [{}]", result.join(", "));
+                dbeer_message!("Pattern '{key}' returns: [{}]", result.join(", "));
                 Ok(())
             }
             Self::Ttl(key) => {
                 let result: Option<String> = connection.ttl(key)?;
-                println!(
+                dbeer_message!(
                     "Key '{key}' remaining time is {}s",
                     result.unwrap_or("0".to_string())
                 );
@@ -94,7 +112,7 @@ impl<'a> Command<'a> {
             }
             Self::FlushAll => {
                 let _: () = connection.flushall()?;
-                println!("  All Keys have been deleted.");
+                dbeer_message!("  All Keys have been deleted.");
                 Ok(())
             }
         }
@@ -1,101 +1,203 @@
-use std::collections::HashMap;
+use std::ffi::{c_int, c_void};
+use std::time::{Duration, Instant};
 
-use sqlite::{Connection, State};
+use sqlite::{Connection, State, Statement, Type, Value, ffi};
 
 use crate::{
     dbeer::{
-        self, Format, Header, Table,
-        query::{is_insert_update_or_delete, split_queries, truncate_field_string},
+        self, Format, Rows, Table,
+        cancel::Canceller,
+        column::ColumnKind,
+        engine::{execute_until_failure, single_statement, transaction_outcome},
+        params::{Param, Params},
+        plan::{Plan, PlanNode},
+        query::{Dialect, is_dml, split_queries},
+        response,
     },
-    dbeer_debug,
+    dbeer_debug, dbeer_message,
 };
 
 pub struct Sqlite {
     queries: String,
     connection: Connection,
+    params: Params,
+    /// Read by the progress handler while the statements run
+    deadline: Option<Box<Instant>>,
 }
 
 impl Sqlite {
     #[allow(clippy::result_large_err)]
-    pub fn connect(db_name: &str, queries: &str) -> dbeer::Result<Self> {
+    pub fn connect(db_name: &str, queries: &str, params: Params) -> dbeer::Result<Self> {
         Ok(Self {
             queries: queries.to_string(),
+            params,
             connection: sqlite::open(db_name).map_err(dbeer::Error::Sqlite)?,
+            deadline: None,
         })
     }
+
+    /// Virtual machine instructions between checks of the deadline
+    const PROGRESS_STEPS: c_int = 1000;
+
+    /// Progress handler. A non zero result interrupts the statement
+    unsafe extern "C" fn past_deadline(deadline: *mut c_void) -> c_int {
+        // SAFETY: the handler is given the deadline kept in the connection
+        let deadline = unsafe { &*(deadline as *const Instant) };
+        (Instant::now() >= *deadline) as c_int
+    }
+
+    fn sqlite_values(values: &[&Param]) -> Vec<Value> {
+        values
+            .iter()
+            .map(|value| match value {
+                Param::Null => Value::Null,
+                Param::Bool(v) => Value::Integer(*v as i64),
+                Param::Int(v) => Value::Integer(*v),
+                Param::Float(v) => Value::Float(*v),
+                Param::Text(v) => Value::String(v.clone()),
+            })
+            .collect()
+    }
+
+    /// Runs one statement, preparing it only when it has values to bind
+    fn run(connection: &Connection, sql: &str, values: &[&Param]) -> sqlite::Result<()> {
+        if values.is_empty() {
+            return connection.execute(sql);
+        }
+
+        let mut stmt = connection.prepare(sql)?;
+        stmt.bind(&Self::sqlite_values(values)[..])?;
+        while let State::Row = stmt.next()? {}
+        Ok(())
+    }
+
+    /// Columns have no fixed type in SQLite, so a kind comes from the storage class
+    /// of the first value of the column which is not NULL. Returns true when one is found
+    fn refine_kinds(stmt: &Statement, kinds: &mut [Option<ColumnKind>]) -> bool {
+        let mut found = false;
+        for (i, kind) in kinds.iter_mut().enumerate() {
+            if kind.is_some() {
+                continue;
+            }
+            *kind = match stmt.column_type(i) {
+                Ok(Type::Integer) => Some(ColumnKind::Integer),
+                Ok(Type::Float) => Some(ColumnKind::Decimal),
+                Ok(Type::Binary) => Some(ColumnKind::Binary),
+                Ok(Type::String) => Some(ColumnKind::Text),
+                _ => None,
+            };
+            found |= kind.is_some();
+        }
+        found
+    }
+
+    /// Renders a cell from its storage class. Only a real NULL is `None`
+    fn read_value(stmt: &Statement, index: usize) -> Option<String> {
+        let value = match stmt.column_type(index) {
+            // TEXT that is not valid UTF-8 is shown as its raw bytes
+            Ok(Type::String) => stmt.read::<Vec<u8>, _>(index).map(|bytes| {
+                String::from_utf8(bytes)
+                    .map_or_else(|e| Value::Binary(e.into_bytes()), Value::String)
+            }),
+            _ => stmt.read::<Value, _>(index),
+        };
+
+        let value = match value {
+            Ok(value) => value,
+            Err(e) => return Some(format!("ERROR: {e}")),
+        };
+
+        match value {
+            Value::Null => None,
+            value => Some(Self::value_to_string(&value)),
+        }
+    }
+
+    fn value_to_string(value: &Value) -> String {
+        match value {
+            Value::Null => "NULL".to_string(),
+            Value::Integer(v) => v.to_string(),
+            Value::Float(v) => format!("{v:?}"),
+            Value::String(v) => v.clone(),
+            // The whole value, the cell shows only the first bytes
+            Value::Binary(bytes) => format!(
+                "x'{}'",
+                bytes.iter().map(|b| format!("{b:02X}")).collect::<String>()
+            ),
+        }
+    }
 }
 
 impl super::SqlExecutor for Sqlite {
+    #[allow(clippy::result_large_err)]
     fn select(&mut self, table: &mut Table) -> dbeer::Result {
-        let mut headers = HashMap::new();
-        headers.insert(1, Header::row_counter());
+        let query = self.queries.clone();
+        table.show_rows(|rows| self.fetch_rows(&query, rows))
+    }
 
+    fn fetch_rows(&mut self, query: &str, rows: &mut dyn Rows) -> dbeer::Result {
+        let (query, values) = self.params.bind(query, Dialect::Sqlite)?;
         let mut stmt = self
             .connection
-            .prepare(&self.queries)
+            .prepare(&query)
+            .map_err(dbeer::Error::Sqlite)?;
+        stmt.bind(&Self::sqlite_values(&values)[..])
             .map_err(dbeer::Error::Sqlite)?;
 
-        for (i, name) in stmt.column_names().iter().enumerate() {
-            headers.insert(i + 2, Header::new(name));
-        }
-
-        let mut rows = Vec::new();
-        let mut counter = 1;
-        while let Ok(State::Row) = stmt.next() {
-            let mut columns = Vec::with_capacity(headers.len());
-            let id_column = format!(" #{counter}");
-            let id_column_length = id_column.len() + 1;
-            columns.push(id_column);
-
-            let column_counter = headers.get_mut(&1).unwrap();
-            if column_counter.length < id_column_length {
-                column_counter.length = id_column_length;
+        rows.columns(stmt.column_names())?;
+        let mut kinds = vec![None; stmt.column_count()];
+        let mut first = true;
+        while let State::Row = stmt.next().map_err(dbeer::Error::Sqlite)? {
+            // Columns with only NULLs so far are told again when a value shows their kind
+            if Self::refine_kinds(&stmt, &mut kinds) || first {
+                rows.column_kinds(
+                    &kinds
+                        .iter()
+                        .map(|kind| kind.unwrap_or_default())
+                        .collect::<Vec<_>>(),
+                );
+                first = false;
             }
-            counter += 1;
-
-            for i in 0..stmt.column_count() {
-                let mut value: String = stmt.read(i).unwrap_or("NULL".to_string());
-                value = truncate_field_string(value);
-                columns.push(format!(" {value}"));
-                let column = headers.get_mut(&(i + 2)).unwrap();
-                let length = value.chars().count() + 2;
-                if column.length < length {
-                    column.length = length;
-                }
+            rows.row(
+                (0..stmt.column_count())
+                    .map(|i| Self::read_value(&stmt, i))
+                    .collect(),
+            )?;
+            if rows.done() {
+                break;
             }
-            rows.push(columns);
-        }
-
-        if rows.is_empty() {
-            println!("  Query has returned 0 results.");
-            return Ok(());
         }
+        Ok(())
+    }
 
-        table.update_headers_and_rows(headers, rows)
+    fn affected_rows(&mut self, query: &str) -> dbeer::Result<u64> {
+        let (sql, values) = self.params.bind(query, Dialect::Sqlite)?;
+        Self::run(&self.connection, &sql, &values).map_err(dbeer::Error::Sqlite)?;
+        Ok(self.connection.change_count() as u64)
     }
 
     fn execute(&mut self, table: &mut Table) -> dbeer::Result {
-        let queries = split_queries(&self.queries);
+        let queries = split_queries(&self.queries, Dialect::Sqlite);
+        let statements = self.params.bind_all(&queries, Dialect::Sqlite)?;
 
         if queries.len() == 1 {
             let query = queries[0];
-            self.connection
-                .execute(query)
-                .map_err(dbeer::Error::Sqlite)?;
+            let (sql, values) = &statements[0];
+            Self::run(&self.connection, sql, values).map_err(dbeer::Error::Sqlite)?;
 
-            if is_insert_update_or_delete(query) {
-                println!("  Row(s) affected: {}", self.connection.change_count());
+            if is_dml(query, Dialect::Sqlite) {
+                response::affected_rows(self.connection.change_count() as u64);
             } else {
-                println!("  Statement executed correctly.");
+                dbeer_message!("  Statement executed correctly.");
             }
             return Ok(());
         }
 
         let mut results = Vec::new();
-        for (i, &query) in queries.iter().enumerate() {
-            let msg = match self.connection.execute(query) {
+        for (i, (&query, (sql, values))) in queries.iter().zip(&statements).enumerate() {
+            let msg = match Self::run(&self.connection, sql, values) {
                 Ok(_) => {
-                    if is_insert_update_or_delete(query) {
+                    if is_dml(query, Dialect::Sqlite) {
                         format!(
                             "{})   Row(s) affected: {}",
                             i + 1,
@@ -113,6 +215,65 @@ impl super::SqlExecutor for Sqlite {
         table.create_execute_result_file(Format::Standard(results))
     }
 
+    fn transaction(&mut self, table: &mut Table) -> dbeer::Result {
+        let queries = split_queries(&self.queries, Dialect::Sqlite);
+        let statements = self.params.bind_all(&queries, Dialect::Sqlite)?;
+        self.connection
+            .execute("BEGIN")
+            .map_err(dbeer::Error::Sqlite)?;
+
+        let mut statements = statements.iter();
+        let (mut results, failed) = execute_until_failure(&queries, Dialect::Sqlite, |_| {
+            let (sql, values) = statements.next().unwrap();
+            Self::run(&self.connection, sql, values).map(|_| self.connection.change_count())
+        });
+
+        self.connection
+            .execute(if failed.is_some() {
+                "ROLLBACK"
+            } else {
+                "COMMIT"
+            })
+            .map_err(dbeer::Error::Sqlite)?;
+        results.push(transaction_outcome(failed));
+
+        table.create_execute_result_file(Format::Standard(results))
+    }
+
+    fn explain(&mut self, table: &mut Table, _analyze: bool) -> dbeer::Result {
+        let (statement, values) = self.params.bind(
+            single_statement(&self.queries, Dialect::Sqlite)?,
+            Dialect::Sqlite,
+        )?;
+        let query = format!("EXPLAIN QUERY PLAN {statement}");
+        dbeer_debug!("Explain query: {query}");
+
+        let mut stmt = self
+            .connection
+            .prepare(&query)
+            .map_err(dbeer::Error::Sqlite)?;
+        stmt.bind(&Self::sqlite_values(&values)[..])
+            .map_err(dbeer::Error::Sqlite)?;
+
+        // SQLite does not estimate costs, only the steps of the plan
+        let mut nodes = Vec::new();
+        while let State::Row = stmt.next().map_err(dbeer::Error::Sqlite)? {
+            nodes.push((
+                stmt.read::<i64, _>("id").map_err(dbeer::Error::Sqlite)?,
+                stmt.read::<i64, _>("parent")
+                    .map_err(dbeer::Error::Sqlite)?,
+                PlanNode::new(
+                    0,
+                    &stmt
+                        .read::<String, _>("detail")
+                        .map_err(dbeer::Error::Sqlite)?,
+                ),
+            ));
+        }
+
+        Plan::from_parents(nodes).render(table)
+    }
+
     fn tables(&mut self) -> crate::dbeer::Result {
         let mut table_names = String::new();
 
@@ -129,7 +290,7 @@ impl super::SqlExecutor for Sqlite {
             .map_err(dbeer::Error::Sqlite)?;
 
         dbeer_debug!("Table names: {table_names}");
-        println!("[{table_names}]");
+        response::table_names(&table_names);
 
         Ok(())
     }
@@ -140,7 +301,178 @@ impl super::SqlExecutor for Sqlite {
         self.select(table)
     }
 
+    fn dialect(&self) -> Dialect {
+        Dialect::Sqlite
+    }
+
+    fn set_queries(&mut self, queries: &str, params: Params) {
+        self.queries = queries.to_string();
+        self.params = params;
+    }
+
+    /// The deadline is the same for every statement of the run
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> dbeer::Result {
+        let deadline = timeout.map(|timeout| Box::new(Instant::now() + timeout));
+        // SAFETY: the handler is replaced before the previous deadline is dropped
+        unsafe {
+            match &deadline {
+                Some(deadline) => ffi::sqlite3_progress_handler(
+                    self.connection.as_raw(),
+                    Self::PROGRESS_STEPS,
+                    Some(Self::past_deadline),
+                    &**deadline as *const Instant as *mut c_void,
+                ),
+                None => ffi::sqlite3_progress_handler(
+                    self.connection.as_raw(),
+                    0,
+                    None,
+                    std::ptr::null_mut(),
+                ),
+            }
+        }
+        self.deadline = deadline;
+        Ok(())
+    }
+
+    fn canceller(&self) -> Option<Canceller> {
+        let connection = self.connection.as_raw() as usize;
+        // SAFETY: sqlite3_interrupt can be called from any thread.
+        // The canceller is dropped when the run ends, before the connection is closed
+        Some(Box::new(move || unsafe {
+            ffi::sqlite3_interrupt(connection as *mut ffi::sqlite3)
+        }))
+    }
+
     fn table_info_query(&self) -> String {
         format!(r#"PRAGMA table_info("{}")"#, self.queries)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use sqlite::Value;
+
+    use crate::dbeer::{
+        ResultSet, Table,
+        column::ColumnKind,
+        engine::{SqlExecutor, Sqlite},
+        params::Params,
+    };
+
+    #[test]
+    fn test_value_to_string() {
+        assert_eq!(Sqlite::value_to_string(&Value::Null), "NULL");
+        assert_eq!(Sqlite::value_to_string(&Value::Integer(-42)), "-42");
+        assert_eq!(Sqlite::value_to_string(&Value::Float(1.0)), "1.0");
+        assert_eq!(Sqlite::value_to_string(&Value::Float(0.25)), "0.25");
+        assert_eq!(
+            Sqlite::value_to_string(&Value::String("NULL".to_string())),
+            "NULL"
+        );
+        assert_eq!(
+            Sqlite::value_to_string(&Value::Binary(vec![0xDE, 0xAD, 0xBE, 0xEF])),
+            "x'DEADBEEF'"
+        );
+        assert_eq!(
+            Sqlite::value_to_string(&Value::Binary(vec![0; 20])),
+            format!("x'{}'", "00".repeat(20))
+        );
+    }
+
+    #[test]
+    fn test_read_value() {
+        let connection = sqlite::open(":memory:").unwrap();
+        let mut stmt = connection
+            .prepare("SELECT NULL, 7, 2.5, 'text', x'0102', CAST(x'FF' AS TEXT)")
+            .unwrap();
+        stmt.next().unwrap();
+
+        let values = (0..stmt.column_count())
+            .map(|i| Sqlite::read_value(&stmt, i))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            values,
+            vec![
+                None,
+                Some("7".to_string()),
+                Some("2.5".to_string()),
+                Some("text".to_string()),
+                Some("x'0102'".to_string()),
+                Some("x'FF'".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_kinds_after_null() {
+        let mut sqlite = Sqlite::connect(":memory:", "", Params::None).unwrap();
+        let mut result_set = ResultSet::default();
+        sqlite
+            .fetch_rows(
+                "SELECT NULL, 1 UNION ALL SELECT x'CAFE', 2",
+                &mut result_set,
+            )
+            .unwrap();
+
+        assert_eq!(
+            result_set.kinds,
+            vec![ColumnKind::Binary, ColumnKind::Integer]
+        );
+        assert_eq!(result_set.values[1][0].as_deref(), Some("x'CAFE'"));
+    }
+
+    #[test]
+    fn test_transaction_rolls_back_on_failure() {
+        let mut sqlite = Sqlite::connect(
+            ":memory:",
+            "INSERT INTO numbers VALUES (1); INSERT INTO missing VALUES (2);",
+            Params::None,
+        )
+        .unwrap();
+        sqlite
+            .connection
+            .execute("CREATE TABLE numbers (n INTEGER)")
+            .unwrap();
+
+        let mut table = Table {
+            dest_folder: std::env::temp_dir().to_string_lossy().to_string(),
+            ..Table::default()
+        };
+        sqlite.transaction(&mut table).unwrap();
+
+        let mut stmt = sqlite
+            .connection
+            .prepare("SELECT COUNT(*) FROM numbers")
+            .unwrap();
+        stmt.next().unwrap();
+        assert_eq!(stmt.read::<i64, _>(0).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_execute_binds_params() {
+        let mut sqlite = Sqlite::connect(
+            ":memory:",
+            "INSERT INTO names VALUES (:id, :name)",
+            Params::parse(r#"{"name": "O'Brien'); DROP TABLE names; --", "id": 1}"#).unwrap(),
+        )
+        .unwrap();
+        sqlite
+            .connection
+            .execute("CREATE TABLE names (id INTEGER, name TEXT)")
+            .unwrap();
+
+        sqlite.execute(&mut Table::default()).unwrap();
+
+        let mut stmt = sqlite
+            .connection
+            .prepare("SELECT id, name FROM names")
+            .unwrap();
+        stmt.next().unwrap();
+        assert_eq!(stmt.read::<i64, _>(0).unwrap(), 1);
+        assert_eq!(
+            stmt.read::<String, _>(1).unwrap(),
+            "O'Brien'); DROP TABLE names; --"
+        );
+    }
+}
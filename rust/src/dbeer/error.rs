@@ -8,6 +8,7 @@ pub enum Error {
     MySql(mysql::Error),
     Odbc(odbc::DiagnosticRecord),
     Redis(redis::RedisError),
+    Neo4j(neo4rs::Error),
     Sqlite(sqlite::Error),
     Serde(serde_json::Error),
     Msg(String),
@@ -21,6 +22,52 @@ impl From<redis::RedisError> for Error {
 
 pub type Result<T = ()> = std::result::Result<T, Error>;
 
+impl Error {
+    /// Where the error comes from, `dbeer` for errors of the tool itself
+    pub fn source_name(&self) -> &'static str {
+        match self {
+            Error::Io(_) => "io",
+            Error::Postgres(_) => "postgres",
+            Error::Mongo(_) => "mongo",
+            Error::Bson(_) => "bson",
+            Error::MySql(_) => "mysql",
+            Error::Odbc(_) => "odbc",
+            Error::Redis(_) => "redis",
+            Error::Neo4j(_) => "neo4j",
+            Error::Sqlite(_) => "sqlite",
+            Error::Serde(_) => "json",
+            Error::Msg(_) => "dbeer",
+        }
+    }
+
+    /// Errors after which the connection can not be used anymore
+    pub fn is_connection_lost(&self) -> bool {
+        match self {
+            Error::Io(_) => true,
+            Error::Postgres(e) => e.is_closed(),
+            Error::MySql(e) => matches!(e, mysql::Error::IoError(_) | mysql::Error::DriverError(_)),
+            Error::Redis(e) => e.is_connection_dropped() || e.is_io_error(),
+            // SQLSTATE class 08 is a connection exception
+            Error::Odbc(e) => e.get_raw_state().starts_with(b"08"),
+            _ => false,
+        }
+    }
+
+    /// SQLSTATE or engine error code when the driver gives one
+    pub fn code(&self) -> Option<String> {
+        match self {
+            Error::Postgres(e) => e.code().map(|state| state.code().to_string()),
+            Error::MySql(mysql::Error::MySqlError(e)) => Some(e.state.clone()),
+            Error::Odbc(e) => std::str::from_utf8(e.get_raw_state())
+                .ok()
+                .map(|state| state.trim_end_matches('\0').to_string()),
+            Error::Redis(e) => e.code().map(String::from),
+            Error::Sqlite(e) => e.code.map(|code| code.to_string()),
+            _ => None,
+        }
+    }
+}
+
 impl std::fmt::Display for Error {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -31,6 +78,7 @@ impl std::fmt::Display for Error {
             Error::MySql(e) => write!(f, "MySql error => {e}"),
             Error::Sqlite(e) => write!(f, "Sqlite error => {e}"),
             Error::Redis(e) => write!(f, "Redis error => {e}"),
+            Error::Neo4j(e) => write!(f, "Neo4j error => {e}"),
             Error::Odbc(e) => write!(
                 f,
                 "Odbc error => {}",
@@ -52,6 +100,7 @@ impl std::error::Error for Error {
             Error::MySql(e) => Some(e),
             Error::Sqlite(e) => Some(e),
             Error::Redis(e) => Some(e),
+            Error::Neo4j(e) => Some(e),
             Error::Odbc(e) => Some(e),
             Error::Serde(e) => Some(e),
             Error::Msg(_) => None,
@@ -0,0 +1,235 @@
+use std::borrow::Cow;
+use std::io::{self, Write};
+
+use crate::dbeer::{self, Rows};
+
+/// How the rows of a statement are written with `-output-format`.
+/// Every format but `Table` keeps the values untruncated
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Csv,
+    Tsv,
+    Json,
+    Ndjson,
+    /// INSERT statements, see `InsertScript`
+    Insert,
+}
+
+impl OutputFormat {
+    #[allow(clippy::result_large_err)]
+    pub fn parse(value: &str) -> dbeer::Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "table" => Ok(OutputFormat::Table),
+            "csv" => Ok(OutputFormat::Csv),
+            "tsv" => Ok(OutputFormat::Tsv),
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" => Ok(OutputFormat::Ndjson),
+            "insert" => Ok(OutputFormat::Insert),
+            other => Err(dbeer::Error::Msg(format!(
+                "Output format {other} is not supported. Use table, csv, tsv, json, ndjson or insert"
+            ))),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OutputFormat::Table => "dbeer",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Json => "json",
+            OutputFormat::Ndjson => "ndjson",
+            OutputFormat::Insert => "sql",
+        }
+    }
+
+    /// Vim filetype of the written file
+    pub fn filetype(&self) -> &'static str {
+        match self {
+            OutputFormat::Table => "dbeer",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Json | OutputFormat::Ndjson => "json",
+            OutputFormat::Insert => "sql",
+        }
+    }
+}
+
+/// Writes the rows in a non table format as they are fetched.
+/// NULL is an empty field in CSV and TSV and `null` in JSON
+pub struct Export<W: Write> {
+    format: OutputFormat,
+    writer: W,
+    names: Vec<String>,
+    rows: usize,
+}
+
+impl<W: Write> Export<W> {
+    pub fn new(format: OutputFormat, writer: W) -> Self {
+        Self {
+            format,
+            writer,
+            names: Vec::new(),
+            rows: 0,
+        }
+    }
+
+    /// Closes the JSON array
+    pub fn finish(mut self) -> io::Result<()> {
+        if self.format == OutputFormat::Json {
+            if self.rows > 0 {
+                writeln!(self.writer)?;
+            }
+            writeln!(self.writer, "]")?;
+        }
+        self.writer.flush()
+    }
+
+    fn write_delimited<'a>(
+        &mut self,
+        fields: impl Iterator<Item = Option<&'a str>>,
+    ) -> io::Result<()> {
+        let (delimiter, escape): (&str, fn(&str) -> Cow<'_, str>) = match self.format {
+            OutputFormat::Tsv => ("\t", tsv_field),
+            _ => (",", csv_field),
+        };
+        let line = fields
+            .map(|field| field.map_or(Cow::Borrowed(""), escape))
+            .collect::<Vec<_>>()
+            .join(delimiter);
+        writeln!(self.writer, "{line}")
+    }
+}
+
+impl<W: Write> Rows for Export<W> {
+    fn columns(&mut self, names: &[String]) -> dbeer::Result {
+        let written = match self.format {
+            OutputFormat::Csv | OutputFormat::Tsv => {
+                self.write_delimited(names.iter().map(|name| Some(name.as_str())))
+            }
+            OutputFormat::Json => write!(self.writer, "["),
+            _ => Ok(()),
+        };
+        self.names = names.to_vec();
+        written.map_err(dbeer::Error::Io)
+    }
+
+    fn row(&mut self, values: Vec<Option<String>>) -> dbeer::Result {
+        match self.format {
+            OutputFormat::Csv | OutputFormat::Tsv => {
+                self.write_delimited(values.iter().map(|value| value.as_deref()))
+            }
+            OutputFormat::Json => {
+                let separator = if self.rows > 0 { "," } else { "" };
+                json_object(&self.names, &values)
+                    .and_then(|object| write!(self.writer, "{separator}\n  {object}"))
+            }
+            OutputFormat::Ndjson => json_object(&self.names, &values)
+                .and_then(|object| writeln!(self.writer, "{object}")),
+            _ => Ok(()),
+        }
+        .map_err(dbeer::Error::Io)?;
+
+        self.rows += 1;
+        Ok(())
+    }
+}
+
+/// Quotes the field (RFC 4180) when it holds a separator, a quote or a line break.
+/// Quotes inside are doubled
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// TSV has no quoting, so tabs, line breaks and backslashes are escaped
+fn tsv_field(value: &str) -> Cow<'_, str> {
+    if value.contains(['\t', '\n', '\r', '\\']) {
+        Cow::Owned(
+            value
+                .replace('\\', "\\\\")
+                .replace('\t', "\\t")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r"),
+        )
+    } else {
+        Cow::Borrowed(value)
+    }
+}
+
+/// Keys keep the column order of the statement
+fn json_object(names: &[String], row: &[Option<String>]) -> io::Result<String> {
+    let fields = names
+        .iter()
+        .zip(row)
+        .map(|(name, value)| {
+            Ok(format!(
+                "{}:{}",
+                serde_json::to_string(name)?,
+                serde_json::to_string(value)?
+            ))
+        })
+        .collect::<serde_json::Result<Vec<_>>>()?;
+
+    Ok(format!("{{{}}}", fields.join(",")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Export, OutputFormat};
+    use crate::dbeer::Rows;
+
+    fn export(format: OutputFormat) -> String {
+        let mut out = Vec::new();
+        let mut export = Export::new(format, &mut out);
+        export
+            .columns(&["id".to_string(), "note".to_string()])
+            .unwrap();
+        for row in [
+            vec![Some("1".to_string()), Some("say \"hi\", bye".to_string())],
+            vec![Some("2".to_string()), Some("tab\there\nnext".to_string())],
+            vec![Some("3".to_string()), None],
+        ] {
+            export.row(row).unwrap();
+        }
+        export.finish().unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(OutputFormat::parse("").unwrap(), OutputFormat::Table);
+        assert_eq!(OutputFormat::parse("NDJSON").unwrap(), OutputFormat::Ndjson);
+        assert!(OutputFormat::parse("xml").is_err());
+    }
+
+    #[test]
+    fn test_write() {
+        assert_eq!(
+            export(OutputFormat::Csv),
+            "id,note\n1,\"say \"\"hi\"\", bye\"\n2,\"tab\there\nnext\"\n3,\n"
+        );
+        assert_eq!(
+            export(OutputFormat::Tsv),
+            "id\tnote\n1\tsay \"hi\", bye\n2\ttab\\there\\nnext\n3\t\n"
+        );
+        assert_eq!(
+            export(OutputFormat::Json),
+            concat!(
+                "[\n",
+                "  {\"id\":\"1\",\"note\":\"say \\\"hi\\\", bye\"},\n",
+                "  {\"id\":\"2\",\"note\":\"tab\\there\\nnext\"},\n",
+                "  {\"id\":\"3\",\"note\":null}\n",
+                "]\n"
+            )
+        );
+        assert_eq!(
+            export(OutputFormat::Ndjson).lines().last(),
+            Some("{\"id\":\"3\",\"note\":null}")
+        );
+    }
+}
@@ -0,0 +1,290 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::time::Duration;
+
+use chrono::{DateTime, Local, NaiveDate, SecondsFormat};
+use serde_json::{Value, json};
+
+use crate::{
+    dbeer::{
+        self, Table,
+        column::{CellFormat, ColumnKind},
+        command::{Action, Command},
+        export::OutputFormat,
+        query::{Multiline, Truncation},
+        response::envelope,
+    },
+    dbeer_debug,
+};
+
+/// Name of the history file when only the log file is given
+pub const FILE_NAME: &str = "dbeer_history.ndjson";
+
+/// Execution about to be written to the history file, one JSON object per line:
+/// `{"timestamp":"2025-06-03T10:15:00.120-03:00","engine":"postgres","connection":"Local",
+/// "dbname":"db","action":"run","query":"select 1","duration_ms":12,"row_count":1,
+/// "affected_rows":null,"status":"ok","error":null}`.
+/// Bind parameters and connection strings are not written
+pub struct Entry {
+    path: String,
+    timestamp: DateTime<Local>,
+    engine: String,
+    connection: String,
+    db_name: String,
+    action: &'static str,
+    query: String,
+}
+
+impl Entry {
+    /// `None` when there is no history file or the command lists the history
+    pub fn start(command: &Command) -> Option<Self> {
+        if command.history_file.is_empty() || matches!(command.action, Action::History) {
+            return None;
+        }
+
+        Some(Self {
+            path: command.history_file.clone(),
+            timestamp: Local::now(),
+            engine: command.engine.clone(),
+            connection: command.conn_name.clone(),
+            db_name: command.db_name.clone(),
+            action: command.action.name(),
+            query: command.queries.trim().to_string(),
+        })
+    }
+
+    /// Appends the outcome. Counts and errors come from the response of the command
+    #[allow(clippy::result_large_err)]
+    pub fn write(self, result: &dbeer::Result, elapsed: Duration) -> dbeer::Result {
+        let response = envelope(result, elapsed);
+        let line = json!({
+            "timestamp": self.timestamp.to_rfc3339_opts(SecondsFormat::Millis, false),
+            "engine": self.engine,
+            "connection": self.connection,
+            "dbname": self.db_name,
+            "action": self.action,
+            "query": self.query,
+            "duration_ms": elapsed.as_millis() as u64,
+            "row_count": response["row_count"],
+            "affected_rows": response["affected_rows"],
+            "status": response["status"],
+            "error": response["error"]["message"],
+        });
+
+        // Queries can hold private data, so only the owner reads a new file
+        let mut options = OpenOptions::new();
+        options.create(true).append(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&self.path).map_err(dbeer::Error::Io)?;
+        writeln!(file, "{line}").map_err(dbeer::Error::Io)
+    }
+}
+
+/// Which entries `history` lists
+#[derive(Debug, Default, PartialEq)]
+pub struct Search {
+    /// Case insensitive text in the query, connection, engine or database name
+    text: String,
+    /// Local dates, both included
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+}
+
+impl Search {
+    #[allow(clippy::result_large_err)]
+    pub fn new(text: &str, since: &str, until: &str) -> dbeer::Result<Self> {
+        Ok(Self {
+            text: text.trim().to_lowercase(),
+            since: Self::date(since)?,
+            until: Self::date(until)?,
+        })
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn date(value: &str) -> dbeer::Result<Option<NaiveDate>> {
+        let value = value.trim();
+        if value.is_empty() {
+            return Ok(None);
+        }
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map(Some)
+            .map_err(|_| dbeer::Error::Msg(format!("Date {value} is not valid. Use YYYY-MM-DD")))
+    }
+
+    fn matches(&self, entry: &Value) -> bool {
+        let text = |key: &str| entry[key].as_str().unwrap_or_default().to_lowercase();
+        let date = DateTime::parse_from_rfc3339(entry["timestamp"].as_str().unwrap_or_default())
+            .ok()
+            .map(|timestamp| timestamp.date_naive());
+
+        (self.text.is_empty()
+            || ["query", "connection", "engine", "dbname"]
+                .iter()
+                .any(|key| text(key).contains(&self.text)))
+            && self
+                .since
+                .is_none_or(|since| date.is_some_and(|date| date >= since))
+            && self
+                .until
+                .is_none_or(|until| date.is_some_and(|date| date <= until))
+    }
+}
+
+/// Lists the entries of the history file matching the search, the latest first.
+/// They are written like the rows of a query, so paging and output formats apply
+#[allow(clippy::result_large_err)]
+pub fn show(command: Command) -> dbeer::Result {
+    let search = Search::new(&command.search, &command.since, &command.until)?;
+    let entries = read(&command.history_file, &search)?;
+    dbeer_debug!("History entries found: {}", entries.len());
+
+    let mut table = Table::new(
+        command.dest_folder,
+        command.header_style_link,
+        command.border_style,
+    );
+    table.output_format = OutputFormat::parse(&command.output_format)?;
+    table.cell_format = CellFormat {
+        truncation: Truncation {
+            max_width: command.max_cell_width,
+            multiline: Multiline::parse(&command.multiline)?,
+        },
+        ..CellFormat::default()
+    };
+    table.max_rows = command.max_rows;
+    table.page = command.page;
+
+    table.show_rows(|rows| {
+        let names = [
+            "timestamp",
+            "engine",
+            "connection",
+            "duration_ms",
+            "rows",
+            "status",
+            "query",
+            "error",
+        ];
+        rows.columns(&names.map(String::from))?;
+        rows.column_kinds(&[
+            ColumnKind::Text,
+            ColumnKind::Text,
+            ColumnKind::Text,
+            ColumnKind::Integer,
+            ColumnKind::Integer,
+            ColumnKind::Text,
+            ColumnKind::Text,
+            ColumnKind::Text,
+        ]);
+
+        for entry in entries.iter().rev() {
+            let text = |key: &str| entry[key].as_str().map(String::from);
+            let number = |key: &str| entry[key].as_u64().map(|n| n.to_string());
+            rows.row(vec![
+                text("timestamp"),
+                text("engine"),
+                text("connection"),
+                number("duration_ms"),
+                number("row_count").or_else(|| number("affected_rows")),
+                text("status"),
+                text("query"),
+                text("error"),
+            ])?;
+            if rows.done() {
+                break;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Entries matching the search in file order. Lines which are not JSON are skipped
+#[allow(clippy::result_large_err)]
+fn read(path: &str, search: &Search) -> dbeer::Result<Vec<Value>> {
+    if path.is_empty() {
+        return Err(dbeer::Error::Msg(
+            "History file is not set. Use -history-file".to_string(),
+        ));
+    }
+
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(dbeer::Error::Io(e)),
+    };
+
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.map_err(dbeer::Error::Io)?;
+        match serde_json::from_str::<Value>(&line) {
+            Ok(entry) if search.matches(&entry) => entries.push(entry),
+            Ok(_) => {}
+            Err(e) => dbeer_debug!("History line skipped: {e}"),
+        }
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Entry, Search, read};
+    use crate::dbeer::{self, command::Command};
+
+    #[test]
+    fn test_search() {
+        let entry = serde_json::json!({
+            "timestamp": "2025-06-03T23:30:00.000-03:00",
+            "engine": "postgres",
+            "connection": "Local",
+            "dbname": "db",
+            "query": "SELECT * FROM Dummies",
+        });
+        assert!(Search::default().matches(&entry));
+        assert!(Search::new("dummies", "", "").unwrap().matches(&entry));
+        assert!(
+            Search::new("local", "2025-06-03", "2025-06-03")
+                .unwrap()
+                .matches(&entry)
+        );
+        assert!(!Search::new("", "2025-06-04", "").unwrap().matches(&entry));
+        assert!(!Search::new("mysql", "", "").unwrap().matches(&entry));
+        assert!(Search::new("", "last tuesday", "").is_err());
+    }
+
+    #[test]
+    fn test_write_and_read() {
+        let path = std::env::temp_dir().join(format!("dbeer_history_{}", std::process::id()));
+        let command = Command {
+            engine: "sqlite".to_string(),
+            queries: " select * from t ".to_string(),
+            history_file: path.to_string_lossy().to_string(),
+            ..Command::new()
+        };
+
+        let entry = Entry::start(&command).unwrap();
+        entry.write(&Ok(()), Duration::from_millis(7)).unwrap();
+        let error = Err(dbeer::Error::Msg("no such table: x".to_string()));
+        Entry::start(&command)
+            .unwrap()
+            .write(&error, Duration::ZERO)
+            .unwrap();
+
+        let entries = read(&command.history_file, &Search::default()).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0]["query"], "select * from t");
+        assert_eq!(entries[0]["duration_ms"], 7);
+        assert_eq!(entries[0]["status"], "ok");
+        assert_eq!(entries[1]["status"], "error");
+        assert_eq!(entries[1]["error"], "no such table: x");
+    }
+}
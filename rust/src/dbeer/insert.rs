@@ -0,0 +1,378 @@
+use std::io::{self, Write};
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::dbeer::{
+    self, Rows,
+    column::{ColumnKind, binary_hex},
+    query::Dialect,
+};
+
+/// INSERT statements generated from the rows of a query
+/// with the identifier quoting and literal escaping of the engine
+#[derive(Debug, Clone, PartialEq)]
+pub struct InsertScript {
+    pub dialect: Dialect,
+    /// Target table, one item per part of a qualified name (schema.table)
+    pub table: Vec<String>,
+    /// Rows per statement
+    pub batch: usize,
+}
+
+impl InsertScript {
+    /// MS-SQL accepts up to 1000 rows in a VALUES list
+    const MSSQL_MAX_BATCH: usize = 1000;
+
+    /// Uses `table` when given, otherwise the first table after FROM in the query
+    #[allow(clippy::result_large_err)]
+    pub fn new(dialect: Dialect, table: &str, query: &str, batch: usize) -> dbeer::Result<Self> {
+        let table = if table.trim().is_empty() {
+            Self::source_table(query).ok_or_else(|| {
+                dbeer::Error::Msg(
+                    "The target table could not be found in the query. Set it with -insert-table"
+                        .to_string(),
+                )
+            })?
+        } else {
+            table
+                .split('.')
+                .map(|part| part.trim().to_string())
+                .collect()
+        };
+
+        let batch = match dialect {
+            // Informix has no multi-row VALUES
+            Dialect::Informix => 1,
+            Dialect::MsSql => batch.clamp(1, Self::MSSQL_MAX_BATCH),
+            _ => batch.max(1),
+        };
+
+        Ok(Self {
+            dialect,
+            table,
+            batch,
+        })
+    }
+
+    fn source_table(query: &str) -> Option<Vec<String>> {
+        const PART: &str = r#"(?:[\w$#]+|"[^"]+"|`[^`]+`|\[[^\]]+\])"#;
+        static FROM: LazyLock<Regex> = LazyLock::new(|| {
+            Regex::new(&format!(r"(?i)\bFROM\s+({PART}(?:\s*\.\s*{PART})*)")).unwrap()
+        });
+        static PARTS: LazyLock<Regex> = LazyLock::new(|| Regex::new(PART).unwrap());
+
+        // FROM inside parentheses belongs to a function or a subquery
+        let depth = |end: usize| {
+            query[..end].matches('(').count() as isize - query[..end].matches(')').count() as isize
+        };
+        let name = FROM
+            .captures_iter(query)
+            .filter_map(|captures| captures.get(1))
+            .find(|name| depth(name.start()) == 0)?
+            .as_str();
+        Some(
+            PARTS
+                .find_iter(name)
+                .map(|part| {
+                    part.as_str()
+                        .trim_matches(|c| matches!(c, '"' | '`' | '[' | ']'))
+                        .to_string()
+                })
+                .collect(),
+        )
+    }
+
+    /// `INSERT INTO` target with the column list
+    fn target(&self, names: &[String]) -> String {
+        let table = self
+            .table
+            .iter()
+            .map(|part| self.identifier(part))
+            .collect::<Vec<_>>()
+            .join(".");
+        let columns = names
+            .iter()
+            .map(|name| self.identifier(name))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{table} ({columns})")
+    }
+
+    fn write_statement<W: Write>(
+        &self,
+        writer: &mut W,
+        into: &str,
+        tuples: &[String],
+    ) -> io::Result<()> {
+        match tuples {
+            [] => Ok(()),
+            [tuple] => writeln!(writer, "INSERT INTO {into} VALUES {tuple};"),
+            // Oracle before 23c has no multi-row VALUES
+            _ if self.dialect == Dialect::Oracle => {
+                writeln!(writer, "INSERT ALL")?;
+                for tuple in tuples {
+                    writeln!(writer, "  INTO {into} VALUES {tuple}")?;
+                }
+                writeln!(writer, "SELECT 1 FROM DUAL;")
+            }
+            _ => writeln!(
+                writer,
+                "INSERT INTO {into} VALUES\n  {};",
+                tuples.join(",\n  ")
+            ),
+        }
+    }
+
+    fn tuple(&self, row: &[Option<String>], kinds: &[ColumnKind]) -> String {
+        let values = row
+            .iter()
+            .enumerate()
+            .map(|(i, value)| {
+                self.literal(value.as_deref(), kinds.get(i).copied().unwrap_or_default())
+            })
+            .collect::<Vec<_>>();
+        format!("({})", values.join(", "))
+    }
+
+    fn identifier(&self, name: &str) -> String {
+        match self.dialect {
+            Dialect::MySql => format!("`{}`", name.replace('`', "``")),
+            Dialect::MsSql => format!("[{}]", name.replace(']', "]]")),
+            _ => format!("\"{}\"", name.replace('"', "\"\"")),
+        }
+    }
+
+    /// Numbers and binary values are written as literals of the engine.
+    /// The rest are string literals converted by the engine to the type of the column,
+    /// but for Oracle dates, whose conversion depends on `NLS_DATE_FORMAT`
+    fn literal(&self, value: Option<&str>, kind: ColumnKind) -> String {
+        let Some(value) = value else {
+            return "NULL".to_string();
+        };
+
+        match kind {
+            ColumnKind::Integer | ColumnKind::Decimal if Self::is_number(value) => {
+                return value.to_string();
+            }
+            ColumnKind::Binary => {
+                if let Some(literal) = binary_hex(value).and_then(|hex| self.binary(hex)) {
+                    return literal;
+                }
+            }
+            ColumnKind::Date if self.dialect == Dialect::Oracle => {
+                return format!("DATE {}", self.string(value));
+            }
+            ColumnKind::Timestamp if self.dialect == Dialect::Oracle => {
+                return format!("TIMESTAMP {}", self.string(value));
+            }
+            _ => {}
+        }
+        self.string(value)
+    }
+
+    /// Plain numbers. NaN, Infinity and the like stay strings
+    fn is_number(value: &str) -> bool {
+        value.parse::<f64>().is_ok_and(f64::is_finite)
+            && value
+                .bytes()
+                .all(|b| b.is_ascii_digit() || matches!(b, b'-' | b'+' | b'.' | b'e' | b'E'))
+    }
+
+    /// Hex literal of the engine. Informix has none
+    fn binary(&self, hex: &str) -> Option<String> {
+        match self.dialect {
+            Dialect::MySql | Dialect::Sqlite => Some(format!("X'{hex}'")),
+            Dialect::MsSql => Some(format!("0x{hex}")),
+            Dialect::Postgres => Some(format!("'\\x{hex}'")),
+            Dialect::Oracle => Some(format!("HEXTORAW('{hex}')")),
+            Dialect::Db2 => Some(format!("BX'{hex}'")),
+            Dialect::Informix => None,
+        }
+    }
+
+    fn string(&self, value: &str) -> String {
+        match self.dialect {
+            // Backslash is an escape character unless NO_BACKSLASH_ESCAPES is set
+            Dialect::MySql => format!(
+                "'{}'",
+                value
+                    .replace('\\', "\\\\")
+                    .replace('\'', "''")
+                    .replace('\0', "\\0")
+            ),
+            // N prefix keeps Unicode text in NVARCHAR columns
+            Dialect::MsSql => format!("N'{}'", value.replace('\'', "''")),
+            _ => format!("'{}'", value.replace('\'', "''")),
+        }
+    }
+}
+
+/// Writes the INSERT statements as the rows are fetched.
+/// Only the rows of the current batch are kept in memory
+pub struct InsertRows<W: Write> {
+    script: InsertScript,
+    writer: W,
+    into: String,
+    tuples: Vec<String>,
+    kinds: Vec<ColumnKind>,
+    rows: usize,
+}
+
+impl<W: Write> InsertRows<W> {
+    pub fn new(script: InsertScript, writer: W) -> Self {
+        Self {
+            tuples: Vec::with_capacity(script.batch),
+            script,
+            writer,
+            into: String::new(),
+            kinds: Vec::new(),
+            rows: 0,
+        }
+    }
+
+    /// Writes the last batch
+    pub fn finish(mut self) -> io::Result<()> {
+        if self.rows == 0 {
+            writeln!(self.writer, "-- Query has returned 0 results.")?;
+        }
+        self.script
+            .write_statement(&mut self.writer, &self.into, &self.tuples)?;
+        self.writer.flush()
+    }
+}
+
+impl<W: Write> Rows for InsertRows<W> {
+    fn columns(&mut self, names: &[String]) -> dbeer::Result {
+        self.into = self.script.target(names);
+        Ok(())
+    }
+
+    fn column_kinds(&mut self, kinds: &[ColumnKind]) {
+        self.kinds = kinds.to_vec();
+    }
+
+    fn row(&mut self, values: Vec<Option<String>>) -> dbeer::Result {
+        self.tuples.push(self.script.tuple(&values, &self.kinds));
+        self.rows += 1;
+
+        if self.tuples.len() == self.script.batch {
+            self.script
+                .write_statement(&mut self.writer, &self.into, &self.tuples)
+                .map_err(dbeer::Error::Io)?;
+            self.tuples.clear();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InsertRows, InsertScript};
+    use crate::dbeer::{Rows, column::ColumnKind, query::Dialect};
+
+    fn script(dialect: Dialect, batch: usize) -> String {
+        let insert =
+            InsertScript::new(dialect, "", "SELECT * FROM sales.customer c", batch).unwrap();
+        let mut out = Vec::new();
+        let mut rows = InsertRows::new(insert, &mut out);
+        rows.columns(&["id".to_string(), "name".to_string()])
+            .unwrap();
+        rows.row(vec![
+            Some("1".to_string()),
+            Some("O'Brien \\ co".to_string()),
+        ])
+        .unwrap();
+        rows.row(vec![Some("2".to_string()), None]).unwrap();
+        rows.finish().unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_source_table() {
+        assert_eq!(
+            InsertScript::source_table(
+                "select extract(year from d) from \"My Schema\" . [t] where x"
+            ),
+            Some(vec!["My Schema".to_string(), "t".to_string()])
+        );
+        assert_eq!(InsertScript::source_table("SELECT 1"), None);
+    }
+
+    #[test]
+    fn test_write() {
+        assert_eq!(
+            script(Dialect::Postgres, 1),
+            concat!(
+                "INSERT INTO \"sales\".\"customer\" (\"id\", \"name\") VALUES ('1', 'O''Brien \\ co');\n",
+                "INSERT INTO \"sales\".\"customer\" (\"id\", \"name\") VALUES ('2', NULL);\n",
+            )
+        );
+        assert_eq!(
+            script(Dialect::MySql, 10),
+            concat!(
+                "INSERT INTO `sales`.`customer` (`id`, `name`) VALUES\n",
+                "  ('1', 'O''Brien \\\\ co'),\n",
+                "  ('2', NULL);\n",
+            )
+        );
+        assert_eq!(
+            script(Dialect::MsSql, 1).lines().next(),
+            Some("INSERT INTO [sales].[customer] ([id], [name]) VALUES (N'1', N'O''Brien \\ co');")
+        );
+        assert_eq!(
+            script(Dialect::Oracle, 2),
+            concat!(
+                "INSERT ALL\n",
+                "  INTO \"sales\".\"customer\" (\"id\", \"name\") VALUES ('1', 'O''Brien \\ co')\n",
+                "  INTO \"sales\".\"customer\" (\"id\", \"name\") VALUES ('2', NULL)\n",
+                "SELECT 1 FROM DUAL;\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_literals_by_kind() {
+        let tuple = |dialect: Dialect| {
+            let insert = InsertScript::new(dialect, "t", "", 1).unwrap();
+            insert.tuple(
+                &[
+                    Some("-12.5".to_string()),
+                    Some("NaN".to_string()),
+                    Some("0xDEADBEEF".to_string()),
+                    Some("2025-06-03 10:15:00".to_string()),
+                ],
+                &[
+                    ColumnKind::Decimal,
+                    ColumnKind::Decimal,
+                    ColumnKind::Binary,
+                    ColumnKind::Timestamp,
+                ],
+            )
+        };
+
+        assert_eq!(
+            tuple(Dialect::MsSql),
+            "(-12.5, N'NaN', 0xDEADBEEF, N'2025-06-03 10:15:00')"
+        );
+        assert_eq!(
+            tuple(Dialect::MySql),
+            "(-12.5, 'NaN', X'DEADBEEF', '2025-06-03 10:15:00')"
+        );
+        assert_eq!(
+            tuple(Dialect::Oracle),
+            "(-12.5, 'NaN', HEXTORAW('DEADBEEF'), TIMESTAMP '2025-06-03 10:15:00')"
+        );
+
+        let insert = InsertScript::new(Dialect::Sqlite, "t", "", 1).unwrap();
+        assert_eq!(
+            insert.tuple(&[Some("x'CAFE'".to_string())], &[ColumnKind::Binary]),
+            "(X'CAFE')"
+        );
+        let insert = InsertScript::new(Dialect::Postgres, "t", "", 1).unwrap();
+        assert_eq!(
+            insert.tuple(&[Some("\\xcafe".to_string())], &[ColumnKind::Binary]),
+            "('\\xcafe')"
+        );
+    }
+}
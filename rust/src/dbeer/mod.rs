@@ -1,12 +1,23 @@
 mod border;
+pub mod cancel;
+pub mod column;
 pub mod command;
 pub mod dispatch;
 pub mod engine;
 mod error;
+pub mod export;
+pub mod history;
+pub mod insert;
 pub mod logger;
+pub mod params;
+pub mod plan;
+pub mod profile;
 pub mod query;
+pub mod response;
+pub mod secret;
+pub mod serve;
 mod table;
 
 pub use border::*;
 pub use error::*;
-pub use table::{Format, Header, Table};
+pub use table::{Format, Header, Page, ResultSet, Rows, Section, Table};
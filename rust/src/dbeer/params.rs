@@ -0,0 +1,356 @@
+use serde_json::Value;
+
+use crate::dbeer::{self, query::Dialect};
+
+/// Value bound to a placeholder
+#[derive(Debug, Clone, PartialEq)]
+pub enum Param {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Text(String),
+}
+
+impl From<Value> for Param {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Null => Param::Null,
+            Value::Bool(v) => Param::Bool(v),
+            Value::Number(v) => match v.as_i64() {
+                Some(i) => Param::Int(i),
+                None => Param::Float(v.as_f64().unwrap_or_default()),
+            },
+            Value::String(v) => Param::Text(v),
+            // Arrays and objects are sent as JSON text
+            other => Param::Text(other.to_string()),
+        }
+    }
+}
+
+/// Values passed with `-params`. A JSON object binds `:name` placeholders
+/// and a JSON array binds `$1` and `?` placeholders. In Postgres `?` is an operator
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Params {
+    #[default]
+    None,
+    Named(Vec<(String, Param)>),
+    Positional(Vec<Param>),
+}
+
+/// Placeholder syntax understood by the driver
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Style {
+    /// `$1`, `$2`... (Postgres)
+    Dollar,
+    /// `?` (MySQL, SQLite and ODBC)
+    Question,
+}
+
+impl From<Dialect> for Style {
+    fn from(dialect: Dialect) -> Self {
+        match dialect {
+            Dialect::Postgres => Style::Dollar,
+            _ => Style::Question,
+        }
+    }
+}
+
+impl Params {
+    #[allow(clippy::result_large_err)]
+    pub fn parse(json: &str) -> dbeer::Result<Self> {
+        if json.trim().is_empty() {
+            return Ok(Params::None);
+        }
+
+        match serde_json::from_str(json).map_err(dbeer::Error::Serde)? {
+            Value::Object(map) => Ok(Params::Named(
+                map.into_iter().map(|(k, v)| (k, v.into())).collect(),
+            )),
+            Value::Array(values) => Ok(Params::Positional(
+                values.into_iter().map(Param::from).collect(),
+            )),
+            Value::Null => Ok(Params::None),
+            _ => Err(dbeer::Error::Msg(
+                "-params must be a JSON object or array".to_string(),
+            )),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self {
+            Params::None => true,
+            Params::Named(values) => values.is_empty(),
+            Params::Positional(values) => values.is_empty(),
+        }
+    }
+
+    pub fn named(&self, name: &str) -> Option<&Param> {
+        match self {
+            Params::Named(values) => values.iter().find(|(n, _)| n == name).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    pub fn positional(&self, index: usize) -> Option<&Param> {
+        match self {
+            Params::Positional(values) => values.get(index),
+            _ => None,
+        }
+    }
+
+    /// Rewrites every placeholder (`:name`, `$n` or `?`) into the positional
+    /// style of the driver and returns the values in binding order.
+    /// Only the placeholder markers change, values never become part of the SQL
+    #[allow(clippy::result_large_err)]
+    pub fn bind<'a>(
+        &'a self,
+        sql: &str,
+        dialect: Dialect,
+    ) -> dbeer::Result<(String, Vec<&'a Param>)> {
+        if self.is_empty() {
+            return Ok((sql.to_string(), Vec::new()));
+        }
+
+        let mut rewritten = String::with_capacity(sql.len());
+        let mut values = Vec::new();
+        let mut next_question = 0;
+
+        let style = Style::from(dialect);
+        for token in tokenize(sql, dialect) {
+            let value = match token {
+                Token::Text(text) => {
+                    rewritten.push_str(text);
+                    continue;
+                }
+                Token::Named(name) => self.named(name).ok_or_else(|| {
+                    dbeer::Error::Msg(format!("Missing value for placeholder :{name}"))
+                })?,
+                Token::Numbered(number) => number
+                    .checked_sub(1)
+                    .and_then(|index| self.positional(index))
+                    .ok_or_else(|| {
+                        dbeer::Error::Msg(format!("Missing value for placeholder ${number}"))
+                    })?,
+                Token::Question => {
+                    next_question += 1;
+                    self.positional(next_question - 1).ok_or_else(|| {
+                        dbeer::Error::Msg(format!("Missing value for placeholder ?{next_question}"))
+                    })?
+                }
+            };
+
+            values.push(value);
+            match style {
+                Style::Dollar => rewritten.push_str(&format!("${}", values.len())),
+                Style::Question => rewritten.push('?'),
+            }
+        }
+
+        Ok((rewritten, values))
+    }
+
+    /// Binds every statement of a batch before any of them runs,
+    /// so a missing value does not leave the batch half executed
+    #[allow(clippy::result_large_err, clippy::type_complexity)]
+    pub fn bind_all<'a>(
+        &'a self,
+        queries: &[&str],
+        dialect: Dialect,
+    ) -> dbeer::Result<Vec<(String, Vec<&'a Param>)>> {
+        queries
+            .iter()
+            .map(|query| self.bind(query, dialect))
+            .collect()
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token<'a> {
+    Text(&'a str),
+    Named(&'a str),
+    Numbered(usize),
+    Question,
+}
+
+/// Splits the SQL into plain text and placeholders.
+/// Quoted strings, quoted identifiers, comments, dollar quoted bodies
+/// and `::` casts are kept as text. Backslash escapes are read in MySQL strings
+/// and Postgres `E'...'` strings
+fn tokenize(sql: &str, dialect: Dialect) -> Vec<Token<'_>> {
+    let bytes = sql.as_bytes();
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    let is_ident = |b: u8| b.is_ascii_alphanumeric() || b == b'_';
+    let find = |from: usize, pattern: &str| {
+        sql[from..]
+            .find(pattern)
+            .map_or(sql.len(), |p| from + p + pattern.len())
+    };
+
+    while i < bytes.len() {
+        let placeholder = match bytes[i] {
+            quote @ (b'\'' | b'"' | b'`') => {
+                let escapes = match dialect {
+                    Dialect::MySql => quote != b'`',
+                    Dialect::Postgres => {
+                        quote == b'\''
+                            && i > 0
+                            && matches!(bytes[i - 1], b'E' | b'e')
+                            && (i < 2 || !is_ident(bytes[i - 2]))
+                    }
+                    _ => false,
+                };
+                i += 1;
+                while i < bytes.len() {
+                    if escapes && bytes[i] == b'\\' {
+                        i += 1;
+                    } else if bytes[i] == quote {
+                        // Doubled quote is an escaped quote
+                        if bytes.get(i + 1) == Some(&quote) {
+                            i += 1;
+                        } else {
+                            break;
+                        }
+                    }
+                    i += 1;
+                }
+                i += 1;
+                None
+            }
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = find(i, "\n");
+                None
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = find(i + 2, "*/");
+                None
+            }
+            b':' if bytes.get(i + 1) == Some(&b':') => {
+                i += 2;
+                None
+            }
+            b':' if bytes
+                .get(i + 1)
+                .is_some_and(|b| b.is_ascii_alphabetic() || *b == b'_') =>
+            {
+                let end = (i + 1..bytes.len())
+                    .find(|&j| !is_ident(bytes[j]))
+                    .unwrap_or(bytes.len());
+                Some((Token::Named(&sql[i + 1..end]), end))
+            }
+            b'$' if bytes.get(i + 1).is_some_and(u8::is_ascii_digit) => {
+                let end = (i + 1..bytes.len())
+                    .find(|&j| !bytes[j].is_ascii_digit())
+                    .unwrap_or(bytes.len());
+                Some((
+                    Token::Numbered(sql[i + 1..end].parse().unwrap_or_default()),
+                    end,
+                ))
+            }
+            b'$' => {
+                // Dollar quoted body: $$...$$ or $tag$...$tag$
+                let tag_end = (i + 1..bytes.len()).find(|&j| !is_ident(bytes[j]));
+                match tag_end {
+                    Some(end) if bytes[end] == b'$' => {
+                        i = find(end + 1, &sql[i..=end]);
+                    }
+                    _ => i += 1,
+                }
+                None
+            }
+            b'?' if dialect != Dialect::Postgres => Some((Token::Question, i + 1)),
+            _ => {
+                i += 1;
+                None
+            }
+        };
+
+        if let Some((token, end)) = placeholder {
+            if start < i {
+                tokens.push(Token::Text(&sql[start..i]));
+            }
+            tokens.push(token);
+            start = end;
+            i = end;
+        }
+    }
+
+    if start < sql.len() {
+        tokens.push(Token::Text(&sql[start..]));
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Param, Params};
+    use crate::dbeer::query::Dialect;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Params::parse("").unwrap(), Params::None);
+        assert_eq!(
+            Params::parse(r#"[1, 2.5, "a", null, true]"#).unwrap(),
+            Params::Positional(vec![
+                Param::Int(1),
+                Param::Float(2.5),
+                Param::Text("a".to_string()),
+                Param::Null,
+                Param::Bool(true)
+            ])
+        );
+        assert!(Params::parse("1").is_err());
+    }
+
+    #[test]
+    fn test_bind() {
+        let named = Params::parse(r#"{"id": 7, "name": "x"}"#).unwrap();
+        let (sql, values) = named
+            .bind(
+                "SELECT ':id', id::text FROM t WHERE id = :id AND name = :name -- :other",
+                Dialect::Postgres,
+            )
+            .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT ':id', id::text FROM t WHERE id = $1 AND name = $2 -- :other"
+        );
+        assert_eq!(values, vec![&Param::Int(7), &Param::Text("x".to_string())]);
+
+        let positional = Params::parse(r#"[1, "b"]"#).unwrap();
+        let (sql, values) = positional
+            .bind(
+                "UPDATE t SET b = $2 WHERE a = $1 AND c = $$?$$",
+                Dialect::MySql,
+            )
+            .unwrap();
+        assert_eq!(sql, "UPDATE t SET b = ? WHERE a = ? AND c = $$?$$");
+        assert_eq!(values, vec![&Param::Text("b".to_string()), &Param::Int(1)]);
+
+        assert!(positional.bind("SELECT ?, ?, ?", Dialect::MySql).is_err());
+        assert!(named.bind("SELECT :missing", Dialect::Postgres).is_err());
+    }
+
+    #[test]
+    fn test_bind_escapes() {
+        let positional = Params::parse(r#"[1]"#).unwrap();
+
+        let (sql, values) = positional
+            .bind(r"SELECT 'it\'s ?', ? FROM t", Dialect::MySql)
+            .unwrap();
+        assert_eq!(sql, r"SELECT 'it\'s ?', ? FROM t");
+        assert_eq!(values, vec![&Param::Int(1)]);
+
+        let (sql, values) = positional
+            .bind(
+                r"SELECT doc ? 'key', E'it\'s $2' FROM t WHERE id = $1",
+                Dialect::Postgres,
+            )
+            .unwrap();
+        assert_eq!(sql, r"SELECT doc ? 'key', E'it\'s $2' FROM t WHERE id = $1");
+        assert_eq!(values, vec![&Param::Int(1)]);
+    }
+}
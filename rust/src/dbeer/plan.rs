@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use crate::{
+    dbeer::{self, Header, Table, query::display_width},
+    dbeer_message,
+};
+
+/// One operation of a query plan
+#[derive(Debug, Default, PartialEq)]
+pub struct PlanNode {
+    pub depth: usize,
+    pub operation: String,
+    pub cost: String,
+    pub rows: String,
+    pub actual: String,
+}
+
+impl PlanNode {
+    pub fn new(depth: usize, operation: &str) -> Self {
+        Self {
+            depth,
+            operation: operation.trim().to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Query plan rendered as an indented tree through `Table`
+#[derive(Debug, Default)]
+pub struct Plan {
+    pub nodes: Vec<PlanNode>,
+}
+
+impl Plan {
+    /// Builds the plan from flat nodes which know their id and their parent id.
+    /// Nodes are kept in the order returned by the engine
+    pub fn from_parents(nodes: Vec<(i64, i64, PlanNode)>) -> Self {
+        let depths: HashMap<i64, usize> =
+            nodes
+                .iter()
+                .fold(HashMap::new(), |mut depths, (id, parent, _)| {
+                    let depth = depths.get(parent).map_or(0, |depth| depth + 1);
+                    depths.insert(*id, depth);
+                    depths
+                });
+
+        Self {
+            nodes: nodes
+                .into_iter()
+                .map(|(id, _, node)| PlanNode {
+                    depth: depths[&id],
+                    ..node
+                })
+                .collect(),
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn render(&self, table: &mut Table) -> dbeer::Result {
+        if self.nodes.is_empty() {
+            dbeer_message!("  Query plan is empty.");
+            return Ok(());
+        }
+
+        // SQLite, for example, does not estimate costs
+        let with_cost = self.nodes.iter().any(|node| !node.cost.is_empty());
+        let with_rows = self.nodes.iter().any(|node| !node.rows.is_empty());
+        let with_actual = self.nodes.iter().any(|node| !node.actual.is_empty());
+
+        let mut names = vec!["plan"];
+        if with_cost {
+            names.push("cost");
+        }
+        if with_rows {
+            names.push("rows");
+        }
+        if with_actual {
+            names.push("actual");
+        }
+
+        let mut headers: HashMap<_, _> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (i + 2, Header::new(name)))
+            .collect();
+        headers.insert(1, Header::row_counter());
+
+        let mut rows = Vec::with_capacity(self.nodes.len());
+        for (i, node) in self.nodes.iter().enumerate() {
+            let arrow = if node.depth > 0 { "-> " } else { "" };
+            let mut values = vec![
+                format!(" #{}", i + 1),
+                format!(" {}{arrow}{}", "   ".repeat(node.depth), node.operation),
+            ];
+            if with_cost {
+                values.push(format!(" {}", node.cost));
+            }
+            if with_rows {
+                values.push(format!(" {}", node.rows));
+            }
+            if with_actual {
+                values.push(format!(" {}", node.actual));
+            }
+
+            for (key, value) in values.iter().enumerate() {
+                let header = headers.get_mut(&(key + 1)).unwrap();
+                let length = display_width(value) + 1;
+                if header.length < length {
+                    header.length = length;
+                }
+            }
+            rows.push(values);
+        }
+
+        table.update_headers_and_rows(headers, rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Plan, PlanNode};
+
+    #[test]
+    fn test_from_parents() {
+        let plan = Plan::from_parents(vec![
+            (2, 0, PlanNode::new(0, "SCAN a")),
+            (5, 2, PlanNode::new(0, "SEARCH b USING INDEX b_idx (id=?)")),
+            (9, 5, PlanNode::new(0, "USE TEMP B-TREE FOR ORDER BY")),
+            (12, 0, PlanNode::new(0, "SCAN c")),
+        ]);
+
+        assert_eq!(
+            plan.nodes.iter().map(|n| n.depth).collect::<Vec<_>>(),
+            vec![0, 1, 2, 0]
+        );
+    }
+}
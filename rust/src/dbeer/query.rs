@@ -1,57 +1,653 @@
-use regex::Regex;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::dbeer;
 
 pub fn truncate_field_string<T: ToString>(field: T) -> String {
-    let mut string = field.to_string();
+    Truncation::default().apply(&field.to_string())
+}
+
+/// Columns a text takes in a terminal. Wide characters (CJK, emoji) take two
+/// and a grapheme cluster (a letter with combining marks, an emoji sequence) counts once
+pub fn display_width(text: &str) -> usize {
+    text.graphemes(true).map(UnicodeWidthStr::width).sum()
+}
+
+/// How a value with line breaks is shown in a single table line
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Multiline {
+    /// First line followed by "..."
+    #[default]
+    First,
+    /// Lines joined with a space
+    Join,
+    /// Line breaks written as \n
+    Escape,
+}
 
-    if string.lines().count() > 1 {
-        string = format!("{}...", string.lines().next().unwrap_or_default());
+impl Multiline {
+    #[allow(clippy::result_large_err)]
+    pub fn parse(value: &str) -> dbeer::Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "first" => Ok(Multiline::First),
+            "join" => Ok(Multiline::Join),
+            "escape" => Ok(Multiline::Escape),
+            other => Err(dbeer::Error::Msg(format!(
+                "Multiline policy {other} is not supported. Use first, join or escape"
+            ))),
+        }
     }
+}
+
+/// Limits of the values shown in table cells
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Truncation {
+    /// Display width kept of a value, 0 keeps all of it
+    pub max_width: usize,
+    pub multiline: Multiline,
+}
 
-    if string.len() > 100 {
-        string = format!("{}...", &string[..100]);
+impl Default for Truncation {
+    fn default() -> Self {
+        Self {
+            max_width: 100,
+            multiline: Multiline::default(),
+        }
     }
-    string
 }
 
-pub fn is_select_query(query: &str) -> bool {
-    query.trim().to_lowercase().starts_with("select")
+impl Truncation {
+    /// Value as shown in a table cell. It is cut between grapheme clusters
+    pub fn apply(&self, value: &str) -> String {
+        let line_break = ['\n', '\r'];
+        let (mut string, mut cut) = match self.multiline {
+            Multiline::First if value.contains(line_break) => (
+                value
+                    .split(line_break)
+                    .next()
+                    .unwrap_or_default()
+                    .to_string(),
+                true,
+            ),
+            Multiline::Join if value.contains(line_break) => (
+                value
+                    .split(line_break)
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                false,
+            ),
+            Multiline::Escape if value.contains(line_break) => {
+                (value.replace('\r', "\\r").replace('\n', "\\n"), false)
+            }
+            _ => (value.to_string(), false),
+        };
+
+        if self.max_width > 0 && display_width(&string) > self.max_width {
+            let mut width = 0;
+            let end = string
+                .grapheme_indices(true)
+                .find(|(_, grapheme)| {
+                    width += grapheme.width();
+                    width > self.max_width
+                })
+                .map_or(string.len(), |(index, _)| index);
+            string.truncate(end);
+            cut = true;
+        }
+
+        if cut {
+            string.push_str("...");
+        }
+        string
+    }
 }
 
-pub fn split_queries(queries: &str) -> Vec<&str> {
-    queries
-        .split(";")
-        .map(|query| query.trim())
-        .filter(|&query| !query.is_empty())
-        .collect::<Vec<_>>()
+/// What running a statement gives back
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StatementKind {
+    /// Rows to show as a table
+    Rows,
+    /// A count of affected rows
+    Dml,
+    /// Only success or failure (DDL, SET, GRANT, transaction control...)
+    Ddl,
+    /// A procedure call, which may or may not return rows.
+    /// The result metadata of the driver tells
+    Call,
 }
 
-pub fn is_insert_update_or_delete(query: &str) -> bool {
-    let query = query.trim().to_lowercase();
-    query.contains("insert") || query.contains("update") || query.contains("delete")
+/// Classifies a single statement from its keywords.
+/// Words inside strings, quoted identifiers and comments are not taken into account
+pub fn classify(sql: &str, dialect: Dialect) -> StatementKind {
+    let keywords = keywords(sql, dialect);
+    let top_level = |index: usize| keywords[index].1 == 0;
+    let position =
+        |word: &str| (0..keywords.len()).find(|&i| top_level(i) && keywords[i].0 == word);
+
+    // The statement after the common table expressions decides
+    let main = match keywords.first() {
+        Some((first, _)) if first == "WITH" => keywords.iter().skip(1).find(|(word, depth)| {
+            *depth == 0
+                && matches!(
+                    word.as_str(),
+                    "SELECT" | "INSERT" | "UPDATE" | "DELETE" | "MERGE" | "VALUES" | "TABLE"
+                )
+        }),
+        first => first,
+    };
+
+    match main.map(|(word, _)| word.as_str()).unwrap_or_default() {
+        // SELECT ... INTO creates or fills a table instead of returning rows
+        "SELECT" if position("INTO").is_some() => StatementKind::Dml,
+        "SELECT" | "VALUES" | "TABLE" | "SHOW" | "DESCRIBE" | "DESC" | "EXPLAIN" => {
+            StatementKind::Rows
+        }
+        "PRAGMA" if !sql.contains('=') => StatementKind::Rows,
+        "INSERT" | "UPDATE" | "DELETE" | "MERGE" | "REPLACE" | "UPSERT" => {
+            // MS-SQL: OUTPUT inserted.id / OUTPUT deleted.*
+            let output = position("OUTPUT").is_some_and(|i| {
+                keywords
+                    .get(i + 1)
+                    .is_some_and(|(next, _)| next == "INSERTED" || next == "DELETED")
+            });
+
+            if output || position("RETURNING").is_some() {
+                StatementKind::Rows
+            } else {
+                StatementKind::Dml
+            }
+        }
+        "EXEC" | "EXECUTE" | "CALL" => StatementKind::Call,
+        _ => StatementKind::Ddl,
+    }
 }
 
-pub fn strip_sql_comments(sql: &str) -> String {
-    let re = Regex::new(
-        r#"(?x)                           # verbose mode
-        (                              # capture group 1: strings or comments
-          ' (?: [^'] | '' )* '          # single quoted string, '' is escaped '
-        | \" (?: [^\"] | \"\" )* \"     # double quoted string, "" is escaped "
-        | /\* (?: .|\n)*? \*/           # block comment, non-greedy
-        | -- [^\n]*                     # line comment
-        )
-        "#,
+/// True when the statement returns or may return rows
+pub fn returns_rows(sql: &str, dialect: Dialect) -> bool {
+    matches!(
+        classify(sql, dialect),
+        StatementKind::Rows | StatementKind::Call
     )
-    .unwrap();
+}
+
+pub fn is_dml(sql: &str, dialect: Dialect) -> bool {
+    classify(sql, dialect) == StatementKind::Dml
+}
+
+/// Uppercase keywords with the depth of parentheses they are found at
+fn keywords(sql: &str, dialect: Dialect) -> Vec<(String, usize)> {
+    let bytes = sql.as_bytes();
+    let mut keywords = Vec::new();
+    let mut depth = 0_usize;
+    let mut i = 0;
 
-    re.replace_all(sql, |caps: &regex::Captures| {
-        let m = &caps[0];
-        if m.starts_with('\'') || m.starts_with('\"') {
-            m.to_string()
-        } else {
-            "".to_string()
+    let skip_to = |from: usize, pattern: &str| {
+        sql[from..]
+            .find(pattern)
+            .map_or(sql.len(), |p| from + p + pattern.len())
+    };
+
+    while i < bytes.len() {
+        match bytes[i] {
+            // A doubled quote closes and opens again, which keeps the scan in sync
+            quote @ (b'\'' | b'"' | b'`') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += if bytes[i] == b'\\' && dialect == Dialect::MySql {
+                        2
+                    } else {
+                        1
+                    };
+                }
+                i += 1;
+            }
+            b'[' if dialect == Dialect::MsSql => i = skip_to(i, "]"),
+            b'-' if bytes.get(i + 1) == Some(&b'-') => i = skip_to(i, "\n"),
+            b'#' if dialect == Dialect::MySql => i = skip_to(i, "\n"),
+            b'/' if bytes.get(i + 1) == Some(&b'*') => i = skip_to(i + 2, "*/"),
+            b'(' => {
+                depth += 1;
+                i += 1;
+            }
+            b')' => {
+                depth = depth.saturating_sub(1);
+                i += 1;
+            }
+            b if b.is_ascii_alphabetic() || b == b'_' => {
+                let end = (i..bytes.len())
+                    .find(|&j| !(bytes[j].is_ascii_alphanumeric() || bytes[j] == b'_'))
+                    .unwrap_or(bytes.len());
+                keywords.push((sql[i..end].to_uppercase(), depth));
+                i = end;
+            }
+            _ => i += 1,
         }
-    })
-    .to_string()
+    }
+    keywords
+}
+
+/// SQL flavour of an engine. It decides how a script is split into statements
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Dialect {
+    Postgres,
+    MySql,
+    Sqlite,
+    MsSql,
+    Oracle,
+    Db2,
+    Informix,
+}
+
+/// Statement of a script. `start` and `end` are byte offsets into the script
+/// and `sql` is the text between them, without the delimiter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Statement<'a> {
+    pub sql: &'a str,
+    pub start: usize,
+    pub end: usize,
+}
+
+pub fn split_queries(queries: &str, dialect: Dialect) -> Vec<&str> {
+    split_statements(queries, dialect)
+        .into_iter()
+        .map(|statement| statement.sql)
+        .collect()
+}
+
+/// Splits a script into statements. Delimiters inside strings, quoted identifiers,
+/// comments, dollar quoted bodies and `BEGIN ... END` blocks are ignored.
+/// MySQL `DELIMITER` lines, MS-SQL `GO` and Oracle `/` lines are understood
+pub fn split_statements(script: &str, dialect: Dialect) -> Vec<Statement<'_>> {
+    let mut splitter = Splitter {
+        script,
+        bytes: script.as_bytes(),
+        dialect,
+        pos: 0,
+        delimiter: ";".to_string(),
+        statements: Vec::new(),
+        start: None,
+        end: 0,
+        head: Vec::new(),
+        head_closed: false,
+        depth: 0,
+        opened: false,
+        scanned: 0,
+        line_blank: true,
+    };
+    splitter.run();
+    splitter.statements
+}
+
+/// How the statement in progress treats `;`
+#[derive(Debug, PartialEq)]
+enum Block {
+    /// Any statement without procedural code
+    None,
+    /// CREATE PROCEDURE, FUNCTION, TRIGGER...
+    Routine,
+    /// Oracle package or type, finished by a `/` line
+    Package,
+    /// DECLARE or BEGIN block outside a routine
+    Anonymous,
+}
+
+struct Splitter<'a> {
+    script: &'a str,
+    bytes: &'a [u8],
+    dialect: Dialect,
+    pos: usize,
+    delimiter: String,
+    statements: Vec<Statement<'a>>,
+    /// Offset of the first token of the statement in progress
+    start: Option<usize>,
+    /// End offset of its last token
+    end: usize,
+    /// Leading keywords, used to detect routines and blocks
+    head: Vec<String>,
+    head_closed: bool,
+    depth: usize,
+    /// A BEGIN block was entered
+    opened: bool,
+    /// Offset up to which `line_blank` is known
+    scanned: usize,
+    /// Only whitespace since the last new line
+    line_blank: bool,
+}
+
+impl Splitter<'_> {
+    const HEAD_WORDS: usize = 8;
+
+    fn run(&mut self) {
+        while self.pos < self.bytes.len() {
+            if self.at_line_start() && self.line_command() {
+                continue;
+            }
+
+            if self.delimiter != ";" && self.script[self.pos..].starts_with(&self.delimiter) {
+                self.pos += self.delimiter.len();
+                self.finish();
+                continue;
+            }
+
+            let from = self.pos;
+            match self.bytes[self.pos] {
+                b if b.is_ascii_whitespace() => self.pos += 1,
+                b'-' if self.peek(1) == Some(b'-') => self.skip_line(),
+                b'#' if self.dialect == Dialect::MySql => self.skip_line(),
+                b'/' if self.peek(1) == Some(b'*') => self.skip_block_comment(),
+                quote @ (b'\'' | b'"') => {
+                    let escapes = self.dialect == Dialect::MySql;
+                    self.skip_quoted(quote, escapes);
+                    self.token(from);
+                }
+                b'`' if self.dialect == Dialect::MySql => {
+                    self.skip_quoted(b'`', false);
+                    self.token(from);
+                }
+                b'[' if self.dialect == Dialect::MsSql => {
+                    self.pos = self.find(self.pos + 1, "]");
+                    self.token(from);
+                }
+                b'$' if self.dialect == Dialect::Postgres => {
+                    self.skip_dollar_quoted();
+                    self.token(from);
+                }
+                b';' => {
+                    self.pos += 1;
+                    if self.delimiter == ";" && self.ends_statement() {
+                        if self.dialect == Dialect::Oracle && self.block() != Block::None {
+                            // PL/SQL needs the semicolon after the last END
+                            self.token(from);
+                        }
+                        self.finish();
+                    } else {
+                        self.token(from);
+                    }
+                }
+                b if b.is_ascii_alphabetic() || b == b'_' => self.word(),
+                b => {
+                    self.pos += self.script[self.pos..]
+                        .chars()
+                        .next()
+                        .map_or(1, char::len_utf8);
+                    if b == b'(' {
+                        self.head_closed = true;
+                    }
+                    self.token(from);
+                }
+            }
+        }
+        self.finish();
+    }
+
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.bytes.get(self.pos + offset).copied()
+    }
+
+    /// Offset right after the next `pattern`, or the end of the script
+    fn find(&self, from: usize, pattern: &str) -> usize {
+        self.script[from.min(self.script.len())..]
+            .find(pattern)
+            .map_or(self.script.len(), |p| from + p + pattern.len())
+    }
+
+    /// Only the bytes after the last call are read, so the whole script is read once
+    fn at_line_start(&mut self) -> bool {
+        for &b in &self.bytes[self.scanned.min(self.pos)..self.pos] {
+            if b == b'\n' {
+                self.line_blank = true;
+            } else if !b.is_ascii_whitespace() {
+                self.line_blank = false;
+            }
+        }
+        self.scanned = self.pos;
+        self.line_blank
+    }
+
+    /// Handles the lines that are commands of the client instead of SQL:
+    /// `DELIMITER` (MySQL), `GO` (MS-SQL) and `/` (Oracle)
+    fn line_command(&mut self) -> bool {
+        let line_end = self.find(self.pos, "\n");
+        let line = self.script[self.pos..line_end].trim();
+        let keyword = line.split_whitespace().next().unwrap_or_default();
+
+        let matched = match self.dialect {
+            Dialect::MySql if self.start.is_none() && keyword.eq_ignore_ascii_case("delimiter") => {
+                let delimiter = line[keyword.len()..].trim();
+                if !delimiter.is_empty() {
+                    self.delimiter = delimiter.to_string();
+                }
+                true
+            }
+            Dialect::MsSql => {
+                let mut words = line.split_whitespace();
+                words.next().is_some_and(|go| go.eq_ignore_ascii_case("go"))
+                    && words.all(|count| count.chars().all(|c| c.is_ascii_digit()))
+            }
+            Dialect::Oracle => line == "/",
+            _ => false,
+        };
+
+        if matched {
+            self.finish();
+            self.pos = line_end;
+        }
+        matched
+    }
+
+    fn skip_line(&mut self) {
+        self.pos = self.find(self.pos, "\n");
+    }
+
+    /// Postgres block comments can be nested
+    fn skip_block_comment(&mut self) {
+        let nested = self.dialect == Dialect::Postgres;
+        let mut depth = 0;
+        while self.pos < self.bytes.len() {
+            match (self.bytes[self.pos], self.peek(1)) {
+                (b'/', Some(b'*')) if depth == 0 || nested => {
+                    depth += 1;
+                    self.pos += 2;
+                }
+                (b'*', Some(b'/')) => {
+                    depth -= 1;
+                    self.pos += 2;
+                    if depth == 0 {
+                        return;
+                    }
+                }
+                _ => self.pos += 1,
+            }
+        }
+    }
+
+    /// Doubled quotes are always an escaped quote, backslash only when `escapes`
+    fn skip_quoted(&mut self, quote: u8, escapes: bool) {
+        self.pos += 1;
+        while self.pos < self.bytes.len() {
+            match self.bytes[self.pos] {
+                b'\\' if escapes => self.pos += 2,
+                b if b == quote && self.peek(1) == Some(quote) => self.pos += 2,
+                b if b == quote => {
+                    self.pos += 1;
+                    return;
+                }
+                _ => self.pos += 1,
+            }
+        }
+        self.pos = self.pos.min(self.bytes.len());
+    }
+
+    /// `$$ ... $$` and `$tag$ ... $tag$`. Anything else starting with `$`
+    /// (like a `$1` placeholder) is a single character
+    fn skip_dollar_quoted(&mut self) {
+        let tag_end = (self.pos + 1..self.bytes.len())
+            .find(|&i| !(self.bytes[i].is_ascii_alphanumeric() || self.bytes[i] == b'_'));
+
+        match tag_end {
+            Some(end)
+                if self.bytes[end] == b'$' && !self.peek(1).is_some_and(|b| b.is_ascii_digit()) =>
+            {
+                let tag = &self.script[self.pos..=end];
+                self.pos = self.find(end + 1, tag);
+            }
+            _ => self.pos += 1,
+        }
+    }
+
+    fn read_word(&self, from: usize) -> &str {
+        let end = (from..self.bytes.len())
+            .find(|&i| {
+                let b = self.bytes[i];
+                !(b.is_ascii_alphanumeric() || b == b'_' || b == b'$' || b == b'#')
+            })
+            .unwrap_or(self.bytes.len());
+        let word = &self.script[from..end];
+
+        // A custom delimiter like $$ ends the word: END$$
+        match word.find(self.delimiter.as_str()) {
+            Some(cut) if self.delimiter != ";" => &word[..cut],
+            _ => word,
+        }
+    }
+
+    /// Next keyword after `from`, skipping blanks and comments,
+    /// together with the offset where it ends
+    fn next_word(&self, mut from: usize) -> (String, usize) {
+        loop {
+            let rest = &self.script[from..];
+            let trimmed = rest.trim_start();
+            from += rest.len() - trimmed.len();
+
+            if trimmed.starts_with("--") {
+                from = self.find(from, "\n");
+            } else if trimmed.starts_with("/*") {
+                from = self.find(from, "*/");
+            } else {
+                let word = self.read_word(from);
+                return (word.to_uppercase(), from + word.len());
+            }
+        }
+    }
+
+    fn word(&mut self) {
+        let from = self.pos;
+        let word = self.read_word(from).to_uppercase();
+        self.pos += word.len();
+
+        // Postgres escape string: E'...'
+        if self.dialect == Dialect::Postgres && word == "E" && self.peek(0) == Some(b'\'') {
+            self.skip_quoted(b'\'', true);
+            self.token(from);
+            return;
+        }
+
+        self.token(from);
+        if !self.head_closed && self.head.len() < Self::HEAD_WORDS {
+            self.head.push(word.clone());
+        }
+
+        let block = self.block();
+        if block == Block::None {
+            return;
+        }
+
+        match word.as_str() {
+            "BEGIN" => {
+                let (next, _) = self.next_word(self.pos);
+                let transaction = matches!(
+                    next.as_str(),
+                    "TRAN" | "TRANSACTION" | "DISTRIBUTED" | "WORK"
+                );
+                if !transaction {
+                    self.depth += 1;
+                    self.opened = true;
+                }
+            }
+            "CASE" => self.depth += 1,
+            "END" => match self.next_word(self.pos) {
+                (next, _)
+                    if matches!(next.as_str(), "IF" | "LOOP" | "WHILE" | "REPEAT" | "FOR") => {}
+                (next, end) => {
+                    // END CASE closes the CASE, which must not open another level
+                    if next == "CASE" {
+                        self.pos = end;
+                        self.token(from);
+                    }
+                    self.depth = self.depth.saturating_sub(1);
+                }
+            },
+            _ => {}
+        }
+    }
+
+    fn block(&self) -> Block {
+        let routine = |kinds: &[&str]| {
+            self.head.iter().skip(1).find_map(|word| {
+                if kinds.contains(&word.as_str()) {
+                    Some(true)
+                } else if matches!(
+                    word.as_str(),
+                    "TABLE" | "VIEW" | "INDEX" | "SEQUENCE" | "SCHEMA" | "DATABASE" | "ON" | "AS"
+                ) {
+                    Some(false)
+                } else {
+                    None
+                }
+            }) == Some(true)
+        };
+
+        let first = self.head.first().map(String::as_str).unwrap_or_default();
+        match (self.dialect, first) {
+            (Dialect::Oracle, "CREATE") if routine(&["PACKAGE", "TYPE"]) => Block::Package,
+            (Dialect::Oracle, "DECLARE" | "BEGIN") => Block::Anonymous,
+            (Dialect::MsSql | Dialect::Db2, "BEGIN") => Block::Anonymous,
+            (Dialect::MsSql, "CREATE" | "ALTER")
+                if routine(&["PROCEDURE", "PROC", "FUNCTION", "TRIGGER"]) =>
+            {
+                Block::Routine
+            }
+            (_, "CREATE") if routine(&["PROCEDURE", "FUNCTION", "TRIGGER", "EVENT"]) => {
+                Block::Routine
+            }
+            _ => Block::None,
+        }
+    }
+
+    fn ends_statement(&self) -> bool {
+        match (self.dialect, self.block()) {
+            (_, Block::None) => true,
+            // The body of an MS-SQL routine goes up to the next GO
+            (Dialect::MsSql, Block::Routine) => false,
+            (Dialect::Oracle, Block::Package) => false,
+            // Declarations of a PL/SQL unit come before its BEGIN
+            (Dialect::Oracle, _) => self.opened && self.depth == 0,
+            _ => self.depth == 0,
+        }
+    }
+
+    fn token(&mut self, from: usize) {
+        if self.start.is_none() {
+            self.start = Some(from);
+        }
+        self.end = self.pos.min(self.bytes.len());
+    }
+
+    fn finish(&mut self) {
+        if let Some(start) = self.start.take() {
+            self.statements.push(Statement {
+                sql: &self.script[start..self.end],
+                start,
+                end: self.end,
+            });
+        }
+        self.head.clear();
+        self.head_closed = false;
+        self.depth = 0;
+        self.opened = false;
+    }
 }
 
 #[allow(dead_code)]
@@ -74,7 +670,8 @@ pub fn remove_sql_comments(sql: &str) -> String {
 #[cfg(test)]
 mod tests {
     use crate::dbeer::query::{
-        remove_sql_comments, split_queries, strip_sql_comments, truncate_field_string,
+        Dialect, Multiline, StatementKind, Truncation, classify, display_width,
+        remove_sql_comments, returns_rows, split_queries, split_statements, truncate_field_string,
     };
 
     #[test]
@@ -102,6 +699,43 @@ mod tests {
         assert_eq!(truncate_field_string(input), "Short...");
     }
 
+    #[test]
+    fn test_multibyte_truncation() {
+        let truncation = Truncation {
+            max_width: 5,
+            multiline: Multiline::First,
+        };
+        assert_eq!(truncation.apply("ñandú del sur"), "ñandú...");
+        assert_eq!(truncation.apply("日本語のテキスト"), "日本...");
+        assert_eq!(truncation.apply("🍺🍺🍺"), "🍺🍺...");
+        assert_eq!(truncation.apply("cafe\u{301}s del sur"), "cafe\u{301}s...");
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("日本"), 4);
+        assert_eq!(display_width("🍺"), 2);
+        assert_eq!(display_width("cafe\u{301}"), 4);
+        assert_eq!(display_width("👨‍👩‍👧"), 2);
+    }
+
+    #[test]
+    fn test_multiline_policy() {
+        let join = Truncation {
+            max_width: 0,
+            multiline: Multiline::Join,
+        };
+        assert_eq!(join.apply("SELECT *\r\n  FROM t\n"), "SELECT * FROM t");
+
+        let escape = Truncation {
+            max_width: 0,
+            multiline: Multiline::Escape,
+        };
+        assert_eq!(escape.apply("a\r\nb"), "a\\r\\nb");
+        assert!(Multiline::parse("wrap").is_err());
+    }
+
     #[test]
     fn test_comment_at_end_of_line() {
         let sql = "SELECT * FROM users; -- Get all users";
@@ -128,15 +762,25 @@ mod tests {
     }
 
     #[test]
-    fn test_strip() {
-        let s = r#"-- comment
-        select * from -- comment 
-        -- table /* where */
-        "#;
+    fn test_split_offsets_with_comments() {
+        let script =
+            "-- header\nSELECT 'it\\'s -- x' FROM t; # note; here\n/* a; b */ SELECT 2 -- end";
+        let statements = split_statements(script, Dialect::MySql);
 
-        assert!(!strip_sql_comments(s).contains("table"));
-        assert!(!strip_sql_comments(s).contains("-- comment"));
-        assert!(!strip_sql_comments(s).contains("/* where */"));
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[0].sql, "SELECT 'it\\'s -- x' FROM t");
+        assert_eq!((statements[0].start, statements[0].end), (10, 36));
+        assert_eq!(statements[1].sql, "SELECT 2");
+        assert_eq!(statements[1].start, script.find("SELECT 2").unwrap());
+        for statement in statements {
+            assert_eq!(statement.sql, &script[statement.start..statement.end]);
+        }
+
+        let script = "SELECT $$ -- not a comment; $$ AS body; -- done";
+        assert_eq!(
+            split_queries(script, Dialect::Postgres),
+            vec!["SELECT $$ -- not a comment; $$ AS body"]
+        );
     }
 
     #[test]
@@ -149,12 +793,261 @@ mod tests {
         }
         "#;
 
-        let split = split_queries(s);
+        let split = split_queries(s, Dialect::Postgres);
         assert_eq!(3, split.len());
         assert_eq!("delete * from some2 where id = 1", split[1]);
 
         let s = "drop table dummies;";
-        let split = split_queries(s);
+        let split = split_queries(s, Dialect::Postgres);
         assert_eq!(1, split.len());
     }
+
+    #[test]
+    fn test_split_offsets() {
+        let script = "SELECT 1;  -- first\n  SELECT 2";
+        let statements = split_statements(script, Dialect::Sqlite);
+
+        assert_eq!(statements.len(), 2);
+        assert_eq!((statements[0].start, statements[0].end), (0, 8));
+        assert_eq!((statements[1].start, statements[1].end), (22, 30));
+        for statement in statements {
+            assert_eq!(statement.sql, &script[statement.start..statement.end]);
+        }
+
+        let script = "SELECT 'ñ;' AS año;\nSELECT ü FROM t";
+        assert_eq!(
+            split_queries(script, Dialect::Postgres),
+            vec!["SELECT 'ñ;' AS año", "SELECT ü FROM t"]
+        );
+    }
+
+    #[test]
+    fn test_split_strings_and_comments() {
+        let script = r#"
+        INSERT INTO t VALUES ('a;b', 'it''s; fine'); -- trailing; comment
+        /* block; comment */ SELECT "odd;column" FROM t;
+        SELECT E'escaped\'; quote';
+        ;
+        "#;
+        assert_eq!(
+            split_queries(script, Dialect::Postgres),
+            vec![
+                "INSERT INTO t VALUES ('a;b', 'it''s; fine')",
+                r#"SELECT "odd;column" FROM t"#,
+                r"SELECT E'escaped\'; quote'",
+            ]
+        );
+
+        let script = r"SELECT 'it\'s;' FROM `a;b`; # note; here
+        SELECT 2";
+        assert_eq!(split_queries(script, Dialect::MySql).len(), 2);
+    }
+
+    #[test]
+    fn test_split_dollar_quoted_bodies() {
+        let script = r#"
+        CREATE FUNCTION add_one(i integer) RETURNS integer AS $$
+        BEGIN
+            RETURN i + 1;
+        END;
+        $$ LANGUAGE plpgsql;
+        DO $body$ BEGIN PERFORM 1; END $body$;
+        SELECT add_one($1);
+        "#;
+
+        let split = split_queries(script, Dialect::Postgres);
+        assert_eq!(split.len(), 3);
+        assert!(split[0].ends_with("LANGUAGE plpgsql"));
+        assert_eq!(split[1], "DO $body$ BEGIN PERFORM 1; END $body$");
+        assert_eq!(split[2], "SELECT add_one($1)");
+    }
+
+    #[test]
+    fn test_split_begin_end_blocks() {
+        let script = r#"
+        CREATE TRIGGER audit AFTER UPDATE ON accounts
+        BEGIN
+            INSERT INTO log VALUES (old.id, CASE WHEN new.total > 0 THEN 'up' ELSE 'down' END);
+            UPDATE accounts SET changed = 1 WHERE id = new.id;
+        END;
+        BEGIN TRANSACTION;
+        UPDATE accounts SET total = 0;
+        COMMIT;
+        "#;
+
+        let split = split_queries(script, Dialect::Sqlite);
+        assert_eq!(split.len(), 4);
+        assert!(split[0].ends_with("END"));
+        assert_eq!(split[1], "BEGIN TRANSACTION");
+
+        let script = r#"
+        CREATE PROCEDURE check_total(IN total INT)
+        BEGIN
+            IF total > 0 THEN
+                SELECT 'positive';
+            END IF;
+            CASE total WHEN 0 THEN SELECT 'zero'; ELSE SELECT 'other'; END CASE;
+        END;
+        CALL check_total(1);
+        "#;
+
+        let split = split_queries(script, Dialect::MySql);
+        assert_eq!(split.len(), 2);
+        assert_eq!(split[1], "CALL check_total(1)");
+    }
+
+    #[test]
+    fn test_split_mysql_delimiter() {
+        let script = r#"
+        DELIMITER $$
+        CREATE PROCEDURE two() BEGIN SELECT 1; SELECT 2; END$$
+        DELIMITER ;
+        CALL two();
+        "#;
+
+        assert_eq!(
+            split_queries(script, Dialect::MySql),
+            vec![
+                "CREATE PROCEDURE two() BEGIN SELECT 1; SELECT 2; END",
+                "CALL two()"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_mssql_go() {
+        let script = r#"
+        CREATE PROCEDURE totals AS
+            SELECT 1;
+            SELECT [weird;name] FROM t;
+        GO
+        EXEC totals;
+        BEGIN TRAN; UPDATE t SET a = 1; COMMIT
+        go 2
+        BEGIN
+            PRINT 'block';
+        END
+        "#;
+
+        let split = split_queries(script, Dialect::MsSql);
+        assert_eq!(split.len(), 6);
+        assert!(split[0].ends_with("FROM t;"));
+        assert_eq!(split[1], "EXEC totals");
+        assert_eq!(split[2], "BEGIN TRAN");
+        assert_eq!(split[4], "COMMIT");
+        assert!(split[5].starts_with("BEGIN") && split[5].ends_with("END"));
+    }
+
+    #[test]
+    fn test_split_oracle_blocks() {
+        let script = r#"
+        CREATE OR REPLACE PROCEDURE bump IS
+            counter NUMBER;
+        BEGIN
+            counter := 1;
+            IF counter > 0 THEN
+                NULL;
+            END IF;
+        END;
+        /
+        CREATE OR REPLACE PACKAGE tools AS
+            PROCEDURE bump;
+        END tools;
+        /
+        DECLARE
+            x NUMBER := 0;
+        BEGIN
+            NULL;
+        END;
+        SELECT 1 FROM dual;
+        "#;
+
+        let split = split_queries(script, Dialect::Oracle);
+        assert_eq!(split.len(), 4);
+        assert!(split[0].starts_with("CREATE OR REPLACE PROCEDURE") && split[0].ends_with("END;"));
+        assert!(split[1].ends_with("END tools;"));
+        assert!(split[2].starts_with("DECLARE") && split[2].ends_with("END;"));
+        assert_eq!(split[3], "SELECT 1 FROM dual");
+    }
+
+    #[test]
+    fn test_classify() {
+        let rows = [
+            "select 1",
+            "(SELECT a FROM t) UNION (SELECT b FROM u)",
+            "WITH recent AS (SELECT * FROM t WHERE d > now()) SELECT * FROM recent",
+            "VALUES (1, 'a'), (2, 'b')",
+            "SHOW search_path",
+            "PRAGMA table_info(t)",
+            "DESCRIBE t",
+            "TABLE t",
+            "EXPLAIN SELECT 1",
+            "INSERT INTO t (a) VALUES (1) RETURNING id",
+            "WITH gone AS (DELETE FROM t RETURNING *) SELECT count(*) FROM gone",
+        ];
+        for sql in rows {
+            assert_eq!(
+                classify(sql, Dialect::Postgres),
+                StatementKind::Rows,
+                "{sql}"
+            );
+        }
+
+        let dml = [
+            "insert into t values (1)",
+            "UPDATE t SET note = 'returning soon' WHERE id = 1",
+            "WITH x AS (SELECT 1 AS a) INSERT INTO t SELECT a FROM x",
+            "DELETE FROM t -- RETURNING *",
+            "SELECT * INTO backup FROM t",
+            "MERGE INTO t USING u ON t.id = u.id WHEN MATCHED THEN DELETE",
+        ];
+        for sql in dml {
+            assert_eq!(
+                classify(sql, Dialect::Postgres),
+                StatementKind::Dml,
+                "{sql}"
+            );
+        }
+
+        let ddl = [
+            "CREATE TABLE t (id int, last_updated timestamp, deleted_at timestamp)",
+            "ALTER TABLE t ADD COLUMN inserted_by text",
+            "PRAGMA foreign_keys = ON",
+            "BEGIN",
+            "SET search_path TO public",
+        ];
+        for sql in ddl {
+            assert_eq!(
+                classify(sql, Dialect::Postgres),
+                StatementKind::Ddl,
+                "{sql}"
+            );
+        }
+
+        assert_eq!(
+            classify(
+                "INSERT INTO t OUTPUT inserted.id VALUES (1)",
+                Dialect::MsSql
+            ),
+            StatementKind::Rows
+        );
+        assert_eq!(
+            classify("UPDATE t SET [output] = 1, output = 2", Dialect::MsSql),
+            StatementKind::Dml
+        );
+        assert_eq!(
+            classify(r"SELECT 'it\'s (' INTO @note", Dialect::MySql),
+            StatementKind::Dml
+        );
+
+        for (sql, dialect) in [
+            ("EXEC sp_who", Dialect::MsSql),
+            ("execute dbo.report @year = 2024", Dialect::MsSql),
+            ("CALL refresh_totals(1)", Dialect::MySql),
+            ("call archive()", Dialect::Postgres),
+        ] {
+            assert_eq!(classify(sql, dialect), StatementKind::Call, "{sql}");
+            assert!(returns_rows(sql, dialect), "{sql}");
+        }
+    }
 }
@@ -0,0 +1,238 @@
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use serde_json::{Value, json};
+
+use crate::dbeer;
+
+/// How the result of a command is written to stdout with `-protocol`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Protocol {
+    /// Vim command in the first line and file path in the second one,
+    /// or a message line. Errors start with `[ERROR]`
+    #[default]
+    Text,
+    /// A single JSON object at the end of the command, see `Response`
+    Json,
+}
+
+impl Protocol {
+    #[allow(clippy::result_large_err)]
+    pub fn parse(value: &str) -> dbeer::Result<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "text" => Ok(Protocol::Text),
+            "json" => Ok(Protocol::Json),
+            other => Err(dbeer::Error::Msg(format!(
+                "Protocol {other} is not supported. Use text or json"
+            ))),
+        }
+    }
+}
+
+/// What a command has produced
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    /// Rows written as a table to a .dbeer file
+    Table,
+    /// Rows written in a non table output format
+    Export,
+    /// Results of several statements written to a .dbeer file
+    Script,
+    /// MongoDB documents written as JSON
+    Documents,
+    /// Table, collection or label names
+    Tables,
+    /// Only messages, no file
+    Message,
+}
+
+impl Kind {
+    fn name(&self) -> &'static str {
+        match self {
+            Kind::Table => "table",
+            Kind::Export => "export",
+            Kind::Script => "script",
+            Kind::Documents => "documents",
+            Kind::Tables => "tables",
+            Kind::Message => "message",
+        }
+    }
+}
+
+/// Everything a command writes to stdout. With the text protocol every part is printed
+/// as soon as it is known. With the JSON protocol the parts are kept and printed at the end as:
+/// `{"status":"ok","kind":"table","file":"...","vim_command":"...","layout":{...},
+/// "row_count":3,"affected_rows":null,"tables":null,"messages":[],"elapsed_ms":12,"error":null}`
+#[derive(Debug, Default)]
+struct Response {
+    protocol: Protocol,
+    kind: Option<Kind>,
+    file: Option<String>,
+    vim_command: Option<String>,
+    layout: Option<Value>,
+    row_count: Option<usize>,
+    affected_rows: Option<u64>,
+    tables: Option<Vec<String>>,
+    messages: Vec<String>,
+}
+
+static RESPONSE: LazyLock<Mutex<Response>> = LazyLock::new(|| Mutex::new(Response::default()));
+
+fn with_response(f: impl FnOnce(&mut Response)) {
+    if let Ok(mut response) = RESPONSE.lock() {
+        f(&mut response);
+    }
+}
+
+/// Starts the response of a new command
+pub fn response_init(protocol: Protocol) {
+    with_response(|response| {
+        *response = Response {
+            protocol,
+            ..Response::default()
+        }
+    });
+}
+
+/// File written by the command and the vim command to run when it is opened
+pub fn file(kind: Kind, vim_command: &str, filepath: &str) {
+    with_response(|response| match response.protocol {
+        Protocol::Text => {
+            println!("{vim_command}");
+            println!("{filepath}");
+        }
+        Protocol::Json => {
+            response.kind = Some(kind);
+            response.vim_command = Some(vim_command.to_string());
+            response.file = Some(filepath.to_string());
+        }
+    });
+}
+
+/// Contents of the layout file of the written table
+pub fn layout(layout: &Value) {
+    with_response(|response| {
+        if response.protocol == Protocol::Json {
+            response.layout = Some(layout.clone());
+        }
+    });
+}
+
+/// Rows of the result, only those of the page shown
+pub fn row_count(count: usize) {
+    with_response(|response| response.row_count = Some(count));
+}
+
+/// Rows changed by a DML statement
+pub fn affected_rows(count: u64) {
+    message(format_args!("  Row(s) affected: {count}"));
+    with_response(|response| response.affected_rows = Some(count));
+}
+
+/// Names separated by spaces
+pub fn table_names(names: &str) {
+    with_response(|response| match response.protocol {
+        Protocol::Text => println!("[{names}]"),
+        Protocol::Json => {
+            response.kind = Some(Kind::Tables);
+            response.tables = Some(names.split_whitespace().map(String::from).collect());
+        }
+    });
+}
+
+pub fn message(args: std::fmt::Arguments) {
+    with_response(|response| match response.protocol {
+        Protocol::Text => println!("{args}"),
+        Protocol::Json => response.messages.push(args.to_string()),
+    });
+}
+
+/// Prints the end of the response: the error line with the text protocol,
+/// the whole JSON object with the JSON protocol
+pub fn finish(result: &dbeer::Result, elapsed: Duration) {
+    with_response(|response| match (response.protocol, result) {
+        (Protocol::Text, Ok(())) => {}
+        (Protocol::Text, Err(e)) => println!("[ERROR] {e}"),
+        (Protocol::Json, result) => println!("{}", response.to_json(result, elapsed)),
+    });
+}
+
+/// The JSON object of the response, whatever the protocol
+pub fn envelope(result: &dbeer::Result, elapsed: Duration) -> Value {
+    let mut envelope = Value::Null;
+    with_response(|response| envelope = response.to_json(result, elapsed));
+    envelope
+}
+
+impl Response {
+    fn to_json(&self, result: &dbeer::Result, elapsed: Duration) -> Value {
+        let error = result.as_ref().err().map(|e| {
+            json!({
+                "source": e.source_name(),
+                "code": e.code(),
+                "message": e.to_string(),
+            })
+        });
+
+        json!({
+            "status": if error.is_some() { "error" } else { "ok" },
+            "kind": self.kind.unwrap_or(Kind::Message).name(),
+            "file": self.file,
+            "vim_command": self.vim_command,
+            "layout": self.layout,
+            "row_count": self.row_count,
+            "affected_rows": self.affected_rows,
+            "tables": self.tables,
+            "messages": self.messages,
+            "elapsed_ms": elapsed.as_millis() as u64,
+            "error": error,
+        })
+    }
+}
+
+#[macro_export]
+macro_rules! dbeer_message {
+    ($($arg:tt)*) => {
+        $crate::message(format_args!($($arg)*))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Kind, Protocol, Response};
+    use crate::dbeer;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Protocol::parse("").unwrap(), Protocol::Text);
+        assert_eq!(Protocol::parse("JSON").unwrap(), Protocol::Json);
+        assert!(Protocol::parse("xml").is_err());
+    }
+
+    #[test]
+    fn test_to_json() {
+        let response = Response {
+            protocol: Protocol::Json,
+            kind: Some(Kind::Table),
+            file: Some("/tmp/1.dbeer".to_string()),
+            row_count: Some(2),
+            ..Response::default()
+        };
+        let json = response.to_json(&Ok(()), Duration::from_millis(15));
+        assert_eq!(json["status"], "ok");
+        assert_eq!(json["kind"], "table");
+        assert_eq!(json["file"], "/tmp/1.dbeer");
+        assert_eq!(json["row_count"], 2);
+        assert_eq!(json["elapsed_ms"], 15);
+        assert!(json["error"].is_null());
+
+        let error = Err(dbeer::Error::Msg("Engine x is not supported".to_string()));
+        let json = Response::default().to_json(&error, Duration::ZERO);
+        assert_eq!(json["status"], "error");
+        assert_eq!(json["kind"], "message");
+        assert_eq!(json["error"]["source"], "dbeer");
+        assert_eq!(json["error"]["message"], "Engine x is not supported");
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Instant;
+
+use serde_json::{Map, Value};
+
+use crate::{
+    dbeer::{
+        self,
+        cancel::exit_if_signaled,
+        command::{Action, Command},
+        dispatch::{Connection, run},
+        history::{self, Entry},
+        profile,
+        response::{Protocol, envelope, response_init},
+    },
+    dbeer_debug, dbeer_error,
+};
+
+/// Open connections by engine, connection string and database name
+type Pool = HashMap<(String, String, String), Connection>;
+
+/// Reads one JSON request per line from stdin and writes one JSON response per line
+/// to stdout until stdin is closed. A request has the arguments of the command line
+/// without the dash, plus an optional `id` which the response returns:
+/// `{"id":1,"engine":"postgres","conn-str":"...","queries":"select 1","option":"1"}`.
+/// The response is the object of the JSON protocol (see `response`).
+/// Arguments given to `dbeer serve` are the defaults of every request
+#[allow(clippy::result_large_err)]
+pub fn serve(defaults: Command) -> io::Result<()> {
+    let mut pool = Pool::new();
+
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let started = Instant::now();
+        response_init(Protocol::Json);
+        let request = serde_json::from_str::<Map<String, Value>>(&line);
+        let id = request
+            .as_ref()
+            .ok()
+            .and_then(|request| request.get("id").cloned())
+            .unwrap_or_default();
+
+        let mut entry = None;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            request
+                .map_err(dbeer::Error::Serde)
+                .and_then(|request| command(request, &defaults))
+                .and_then(|command| {
+                    entry = Entry::start(&command);
+                    handle(&mut pool, command)
+                })
+        }))
+        .unwrap_or_else(|_| {
+            Err(dbeer::Error::Msg(
+                "The request has stopped unexpectedly".to_string(),
+            ))
+        });
+        if let Err(e) = &result {
+            dbeer_error!("[ERROR] {e}");
+        }
+
+        let elapsed = started.elapsed();
+        if let Some(entry) = entry
+            && let Err(e) = entry.write(&result, elapsed)
+        {
+            dbeer_error!("[ERROR] History not written: {e}");
+        }
+
+        let mut response = envelope(&result, elapsed);
+        response["id"] = id;
+        let mut stdout = io::stdout().lock();
+        writeln!(stdout, "{response}")?;
+        stdout.flush()?;
+        exit_if_signaled();
+    }
+
+    dbeer_debug!("Closing {} connection(s)", pool.len());
+    Ok(())
+}
+
+/// Command of a request, on top of the arguments of `dbeer serve`
+#[allow(clippy::result_large_err)]
+fn command(request: Map<String, Value>, defaults: &Command) -> dbeer::Result<Command> {
+    let mut command = defaults.clone();
+    for (key, value) in request {
+        let value = match value {
+            Value::String(value) => value,
+            value => value.to_string(),
+        };
+        // Responses are always JSON
+        if key != "id" && (key == "protocol" || !command.set(&format!("-{key}"), value)) {
+            return Err(dbeer::Error::Msg(format!(
+                "Argument {key} is not valid in a request"
+            )));
+        }
+    }
+    profile::apply(&mut command)?;
+    Ok(command)
+}
+
+/// Runs the command on the pooled connection, opening it when there is none.
+/// The connection is closed when it is lost. A panicking run drops it as well,
+/// as it is out of the pool meanwhile
+#[allow(clippy::result_large_err)]
+fn handle(pool: &mut Pool, command: Command) -> dbeer::Result {
+    if let Action::History = command.action {
+        return history::show(command);
+    }
+
+    let key = (
+        command.engine.clone(),
+        command.conn_str.clone(),
+        command.db_name.clone(),
+    );
+
+    let mut connection = match pool.remove(&key) {
+        Some(mut connection) => {
+            dbeer_debug!("Reusing {} connection", command.engine);
+            if let Err(e) = connection.set_queries(&command) {
+                pool.insert(key, connection);
+                return Err(e);
+            }
+            connection
+        }
+        None => Connection::open(&command, command.engine.clone().into())?,
+    };
+
+    let result = run(&mut connection, command);
+    if !result.as_ref().is_err_and(dbeer::Error::is_connection_lost) {
+        pool.insert(key, connection);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Map, Value};
+
+    use crate::dbeer::command::Command;
+
+    fn request(line: &str) -> Map<String, Value> {
+        serde_json::from_str(line).unwrap()
+    }
+
+    #[test]
+    fn test_command() {
+        let defaults = Command {
+            dest_folder: "/data".to_string(),
+            ..Command::new()
+        };
+
+        let command = super::command(
+            request(
+                r#"{"id":7,"engine":"sqlite","dbname":"a.db","queries":"select 1","max-rows":50,"transaction":true}"#,
+            ),
+            &defaults,
+        )
+        .unwrap();
+        assert_eq!(command.engine, "sqlite");
+        assert_eq!(command.max_rows, 50);
+        assert!(command.transaction);
+        assert_eq!(command.dest_folder, "/data");
+
+        assert!(super::command(request(r#"{"protocol":"text"}"#), &defaults).is_err());
+        assert!(super::command(request(r#"{"bogus":1}"#), &defaults).is_err());
+    }
+}
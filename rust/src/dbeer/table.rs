@@ -1,15 +1,25 @@
 use chrono::Local;
 
 use super::border::BorderStyle;
-use crate::{dbeer, dbeer_debug};
+use super::column::{CellFormat, ColumnKind};
+use super::export::{Export, OutputFormat};
+use super::insert::{InsertRows, InsertScript};
+use super::query::display_width;
+use super::response::{self, Kind};
+use crate::{dbeer, dbeer_debug, dbeer_message};
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::{BufWriter, Write};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{env, process};
 
 #[derive(Debug)]
 pub struct Header {
     pub name: String,
     pub length: usize,
+    pub kind: ColumnKind,
 }
 
 impl Header {
@@ -17,22 +27,391 @@ impl Header {
         Self {
             name: "  ".to_string(),
             length: 4,
+            kind: ColumnKind::Text,
         }
     }
 
+    /// Sized by the uppercase name, which can be wider (`ß` is `SS`)
     pub fn new(name: &str) -> Self {
+        let name = name.to_uppercase();
         Header {
-            name: format!(" {}", name.to_uppercase()),
-            length: name.len() + 2,
+            length: display_width(&name) + 2,
+            name: format!(" {name}"),
+            kind: ColumnKind::Text,
+        }
+    }
+
+    /// Row counter followed by a header per column
+    fn from_names(names: &[String]) -> HashMap<usize, Header> {
+        let mut headers: HashMap<_, _> = names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (i + 2, Header::new(name)))
+            .collect();
+        headers.insert(1, Header::row_counter());
+        headers
+    }
+
+    fn set_kinds(headers: &mut HashMap<usize, Header>, kinds: &[ColumnKind]) {
+        for (i, kind) in kinds.iter().enumerate() {
+            if let Some(header) = headers.get_mut(&(i + 2)) {
+                header.kind = *kind;
+            }
+        }
+    }
+
+    /// Widens the column to `length` when it is narrower
+    fn fit(headers: &mut HashMap<usize, Header>, key: usize, length: usize) {
+        if let Some(header) = headers.get_mut(&key)
+            && header.length < length
+        {
+            header.length = length;
         }
     }
 }
 
+/// Columns and values of a row returning statement. `None` is a SQL NULL
+#[derive(Debug, Default)]
+pub struct ResultSet {
+    pub names: Vec<String>,
+    /// Empty when the engine does not tell the column types
+    pub kinds: Vec<ColumnKind>,
+    pub values: Vec<Vec<Option<String>>>,
+}
+
+impl ResultSet {
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Adds the row counter column, formats the values
+    /// and sizes every column to its widest value
+    /// Rows are numbered from `offset` + 1
+    pub fn headers_and_rows(
+        &self,
+        format: &CellFormat,
+        offset: usize,
+    ) -> (HashMap<usize, Header>, Vec<Vec<String>>) {
+        let mut headers = Header::from_names(&self.names);
+        Header::set_kinds(&mut headers, &self.kinds);
+
+        let mut rows = Vec::with_capacity(self.values.len());
+        for (i, row) in self.values.iter().enumerate() {
+            let mut columns = Vec::with_capacity(headers.len());
+            let id_column = format!(" #{}", offset + i + 1);
+            Header::fit(&mut headers, 1, id_column.len() + 1);
+            columns.push(id_column);
+
+            for (column_index, value) in row.iter().enumerate() {
+                let value = format.cell(value.as_deref(), self.kind(column_index));
+                Header::fit(&mut headers, column_index + 2, display_width(&value) + 2);
+                columns.push(format!(" {value}"));
+            }
+            rows.push(columns);
+        }
+
+        (headers, rows)
+    }
+
+    fn kind(&self, column_index: usize) -> ColumnKind {
+        self.kinds.get(column_index).copied().unwrap_or_default()
+    }
+}
+
+/// Receives the rows of a statement while they are fetched
+pub trait Rows {
+    #[allow(clippy::result_large_err)]
+    fn columns(&mut self, names: &[String]) -> dbeer::Result;
+
+    #[allow(clippy::result_large_err)]
+    fn row(&mut self, values: Vec<Option<String>>) -> dbeer::Result;
+
+    /// Kind of every column, sent before the first row by the engines which know it.
+    /// It can be sent again before a later row when a column had only NULLs (SQLite)
+    fn column_kinds(&mut self, _kinds: &[ColumnKind]) {}
+
+    /// True when no more rows are wanted, so the fetch can stop
+    fn done(&self) -> bool {
+        false
+    }
+}
+
+impl Rows for ResultSet {
+    fn columns(&mut self, names: &[String]) -> dbeer::Result {
+        self.names = names.to_vec();
+        Ok(())
+    }
+
+    fn row(&mut self, values: Vec<Option<String>>) -> dbeer::Result {
+        self.values.push(values);
+        Ok(())
+    }
+
+    fn column_kinds(&mut self, kinds: &[ColumnKind]) {
+        self.kinds = kinds.to_vec();
+    }
+}
+
+/// Keeps the rows of one page of `-max-rows` and stops the fetch
+/// as soon as a row past the page shows that more rows exist.
+/// With `max_rows` 0 every row is kept
+pub struct Page<'a> {
+    rows: &'a mut dyn Rows,
+    max_rows: usize,
+    skip: usize,
+    seen: usize,
+    more: bool,
+}
+
+impl<'a> Page<'a> {
+    /// `page` starts at 1
+    pub fn new(rows: &'a mut dyn Rows, max_rows: usize, page: usize) -> Self {
+        Self {
+            rows,
+            max_rows,
+            skip: max_rows * page.saturating_sub(1),
+            seen: 0,
+            more: false,
+        }
+    }
+
+    /// Rows of the page sent to the sink
+    pub fn shown(&self) -> usize {
+        let shown = self.seen.saturating_sub(self.skip);
+        if self.max_rows == 0 {
+            shown
+        } else {
+            shown.min(self.max_rows)
+        }
+    }
+
+    /// Line shown below the rows when they are only a part of the result
+    pub fn footer(&self) -> Option<String> {
+        if self.max_rows == 0 {
+            return None;
+        }
+
+        let page = self.skip / self.max_rows + 1;
+        let shown = self.shown();
+        let range = format!(
+            "  Rows {}-{} (page {page}).",
+            self.skip + 1,
+            self.skip + shown
+        );
+        match (self.more, page) {
+            (true, _) => Some(format!(
+                "{range} More rows available, next page: {}",
+                page + 1
+            )),
+            (false, 1) => None,
+            (false, _) if shown == 0 => Some(format!(
+                "  Page {page} has no rows. Query has returned {} results.",
+                self.seen
+            )),
+            (false, _) => Some(format!("{range} No more rows.")),
+        }
+    }
+}
+
+impl Rows for Page<'_> {
+    fn columns(&mut self, names: &[String]) -> dbeer::Result {
+        self.rows.columns(names)
+    }
+
+    fn column_kinds(&mut self, kinds: &[ColumnKind]) {
+        self.rows.column_kinds(kinds)
+    }
+
+    fn row(&mut self, values: Vec<Option<String>>) -> dbeer::Result {
+        self.seen += 1;
+        if self.seen <= self.skip {
+            return Ok(());
+        }
+        if self.max_rows > 0 && self.seen > self.skip + self.max_rows {
+            self.more = true;
+            return Ok(());
+        }
+        self.rows.row(values)
+    }
+
+    fn done(&self) -> bool {
+        self.more
+    }
+}
+
+/// First pass of a streamed table. The values are spilled to a temporary file
+/// while the width of every column is measured, so no row is kept in memory
+struct TableRows {
+    format: CellFormat,
+    headers: HashMap<usize, Header>,
+    spill_path: PathBuf,
+    spill: BufWriter<File>,
+    count: usize,
+}
+
+impl TableRows {
+    #[allow(clippy::result_large_err)]
+    fn new(format: CellFormat) -> dbeer::Result<Self> {
+        let (spill_path, spill) = Self::create_spill().map_err(dbeer::Error::Io)?;
+
+        Ok(Self {
+            format,
+            headers: HashMap::new(),
+            spill_path,
+            spill: BufWriter::new(spill),
+            count: 0,
+        })
+    }
+
+    /// New file only readable by its owner. The name is never reused,
+    /// so neither another table of the process nor a file planted there is written
+    fn create_spill() -> io::Result<(PathBuf, File)> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+
+        loop {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.subsec_nanos());
+            let path = env::temp_dir().join(format!(
+                "dbeer-{}-{}-{nanos}.spill",
+                process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            match options.open(&path) {
+                Ok(file) => return Ok((path, file)),
+                Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+impl Rows for TableRows {
+    fn columns(&mut self, names: &[String]) -> dbeer::Result {
+        self.headers = Header::from_names(names);
+        Ok(())
+    }
+
+    fn column_kinds(&mut self, kinds: &[ColumnKind]) {
+        Header::set_kinds(&mut self.headers, kinds);
+    }
+
+    fn row(&mut self, values: Vec<Option<String>>) -> dbeer::Result {
+        for (i, value) in values.iter().enumerate() {
+            let cell = self.format.cell(value.as_deref(), self.kind(i));
+            Header::fit(&mut self.headers, i + 2, display_width(&cell) + 2);
+        }
+
+        // Untruncated, the second pass needs them for the cells file
+        serde_json::to_writer(&mut self.spill, &values).map_err(dbeer::Error::Serde)?;
+        self.spill.write_all(b"\n").map_err(dbeer::Error::Io)?;
+        self.count += 1;
+        Ok(())
+    }
+}
+
+impl TableRows {
+    fn kind(&self, column_index: usize) -> ColumnKind {
+        self.headers
+            .get(&(column_index + 2))
+            .map_or(ColumnKind::Text, |header| header.kind)
+    }
+}
+
+impl Drop for TableRows {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.spill_path);
+    }
+}
+
+/// Sidecar of a .dbeer file with the full value of every truncated cell,
+/// one JSON object per line: `{"line":4,"column":2,"value":"..."}`.
+/// `line` is the line of the .dbeer file and `column` counts from the first
+/// column of the statement. Only text and binary cells are kept, numbers and booleans are
+/// formatted but never cut. The file is created with the first truncated cell
+struct CellsFile {
+    path: String,
+    writer: Option<BufWriter<File>>,
+}
+
+impl CellsFile {
+    const EXTENSION: &str = "cells";
+
+    fn new(filepath: &str) -> Self {
+        Self {
+            path: format!("{filepath}.{}", Self::EXTENSION),
+            writer: None,
+        }
+    }
+
+    /// Keeps the value when the cell does not show it as is
+    #[allow(clippy::result_large_err)]
+    fn add(
+        &mut self,
+        line: usize,
+        column: usize,
+        kind: ColumnKind,
+        value: &Option<String>,
+        cell: &str,
+    ) -> dbeer::Result {
+        let Some(value) = value
+            .as_deref()
+            .filter(|value| !kind.is_numeric() && kind != ColumnKind::Boolean && *value != cell)
+        else {
+            return Ok(());
+        };
+
+        let writer = match &mut self.writer {
+            Some(writer) => writer,
+            writer => writer.insert(BufWriter::new(
+                File::create(&self.path).map_err(dbeer::Error::Io)?,
+            )),
+        };
+        serde_json::to_writer(
+            &mut *writer,
+            &serde_json::json!({ "line": line, "column": column, "value": value }),
+        )
+        .map_err(dbeer::Error::Serde)?;
+        writer.write_all(b"\n").map_err(dbeer::Error::Io)
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn finish(self) -> dbeer::Result {
+        match self.writer {
+            Some(mut writer) => writer.flush().map_err(dbeer::Error::Io),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Part of the output of a script with several statements
+pub enum Section {
+    /// Rows of a statement under a title
+    Rows {
+        title: String,
+        result_set: ResultSet,
+        footer: Option<String>,
+    },
+    /// Line of a statement which returns no rows
+    Status(String),
+}
+
 #[derive(Debug, Default)]
 pub struct Table {
     pub dest_folder: String,
     pub header_style_link: String,
     pub border_style: BorderStyle,
+    pub output_format: OutputFormat,
+    pub insert_script: Option<InsertScript>,
+    pub cell_format: CellFormat,
+    /// Rows per page, 0 shows every row
+    pub max_rows: usize,
+    pub page: usize,
     pub headers: HashMap<usize, Header>,
     pub rows: Vec<Vec<String>>,
 }
@@ -44,12 +423,20 @@ pub enum Format {
 
 impl Table {
     const DBEER_EXTENSION: &str = "dbeer";
+    /// Sidecar of a .dbeer file telling where every table is, as JSON:
+    /// `{"tables":[{"header_line":2,"columns":[...],"rows":{...}}]}`.
+    /// Each column has the byte range of its name in the header line (`name_start`, `name_end`)
+    /// and the display columns of its cells (`start`, `end`), the same in every line of the table.
+    /// Row `n` of a table is at line `first_line + (n - first_number) * step`.
+    /// Lines count from 1, bytes and display columns from 0 with the end excluded
+    const LAYOUT_EXTENSION: &str = "layout";
 
     pub fn new(dest_folder: String, header_style_link: String, border_style: BorderStyle) -> Self {
         Self {
             dest_folder,
             header_style_link,
             border_style,
+            page: 1,
             ..Table::default()
         }
     }
@@ -67,38 +454,352 @@ impl Table {
         self.generate()
     }
 
+    /// Streams the rows of a statement into a table or into the selected output format.
+    /// `fetch` sends every row to the sink as soon as it is read
+    #[allow(clippy::result_large_err)]
+    pub fn show_rows(
+        &mut self,
+        fetch: impl FnOnce(&mut dyn Rows) -> dbeer::Result,
+    ) -> dbeer::Result {
+        match self.output_format {
+            OutputFormat::Table => {
+                let mut rows = TableRows::new(self.cell_format)?;
+                let mut page = Page::new(&mut rows, self.max_rows, self.page);
+                fetch(&mut page)?;
+                let footer = page.footer();
+
+                response::row_count(rows.count);
+                // Only the row counter, the statement was a call without a result set
+                if rows.headers.len() <= 1 {
+                    dbeer_message!("  Statement executed correctly.");
+                    return Ok(());
+                }
+                if rows.count == 0 {
+                    dbeer_message!(
+                        "{}",
+                        footer.unwrap_or("  Query has returned 0 results.".to_string())
+                    );
+                    return Ok(());
+                }
+                self.write_table(rows, footer)
+            }
+            OutputFormat::Insert => {
+                let insert_script = self.insert_script.clone().ok_or_else(|| {
+                    dbeer::Error::Msg("The target of the INSERT script is not set".to_string())
+                })?;
+                let mut footer = None;
+                self.export(|writer| {
+                    let mut insert = InsertRows::new(insert_script, writer);
+                    let mut page = Page::new(&mut insert, self.max_rows, self.page);
+                    fetch(&mut page)?;
+                    footer = page.footer();
+                    response::row_count(page.shown());
+                    insert.finish().map_err(dbeer::Error::Io)
+                })?;
+                Self::export_footer(footer);
+                Ok(())
+            }
+            format => {
+                let mut footer = None;
+                self.export(|writer| {
+                    let mut export = Export::new(format, writer);
+                    let mut page = Page::new(&mut export, self.max_rows, self.page);
+                    fetch(&mut page)?;
+                    footer = page.footer();
+                    response::row_count(page.shown());
+                    export.finish().map_err(dbeer::Error::Io)
+                })?;
+                Self::export_footer(footer);
+                Ok(())
+            }
+        }
+    }
+
+    /// An exported page has no room for its footer, it is sent after the file
+    fn export_footer(footer: Option<String>) {
+        if let Some(footer) = footer {
+            dbeer_message!("{footer}");
+        }
+    }
+
+    /// Second pass of a streamed table. Reads the spilled cells back
+    /// and writes the table line by line
+    #[allow(clippy::result_large_err)]
+    fn write_table(&self, mut rows: TableRows, footer: Option<String>) -> dbeer::Result {
+        rows.spill.flush().map_err(dbeer::Error::Io)?;
+        // Rows keep their number in the whole result
+        let offset = self.max_rows * self.page.saturating_sub(1);
+        Header::fit(
+            &mut rows.headers,
+            1,
+            format!(" #{}", offset + rows.count).len() + 1,
+        );
+
+        let filepath = self.create_dbeer_file_format();
+        dbeer_debug!("File path: {filepath}. Streaming {} rows", rows.count);
+
+        let mut writer = BufWriter::new(File::create(&filepath).map_err(dbeer::Error::Io)?);
+        let mut cells_file = CellsFile::new(&filepath);
+        let spill = BufReader::new(File::open(&rows.spill_path).map_err(dbeer::Error::Io)?);
+
+        let mut lines = self.header_lines(&rows.headers);
+        let mut line_number = 0;
+        for (i, line) in spill.lines().enumerate() {
+            let values: Vec<Option<String>> =
+                serde_json::from_str(&line.map_err(dbeer::Error::Io)?)
+                    .map_err(dbeer::Error::Serde)?;
+
+            // The header lines are still pending on the first row
+            let row_line = line_number + lines.len() + 1;
+            let mut row = Vec::with_capacity(values.len() + 1);
+            row.push(format!(" #{}", offset + i + 1));
+            for (column, value) in values.iter().enumerate() {
+                let kind = rows.kind(column);
+                let cell = self.cell_format.cell(value.as_deref(), kind);
+                cells_file.add(row_line, column + 1, kind, value, &cell)?;
+                row.push(format!(" {cell}"));
+            }
+            lines.extend(self.row_lines(&rows.headers, &row, i + 1 == rows.count));
+
+            line_number += lines.len();
+            for line in lines.drain(..) {
+                writeln!(writer, "{line}").map_err(dbeer::Error::Io)?;
+            }
+        }
+        if let Some(footer) = footer {
+            writeln!(writer, "{footer}").map_err(dbeer::Error::Io)?;
+        }
+        writer.flush().map_err(dbeer::Error::Io)?;
+        cells_file.finish()?;
+        Self::write_layout(
+            &filepath,
+            vec![self.table_layout(&rows.headers, 2, rows.count, offset + 1)],
+        )?;
+
+        response::file(Kind::Table, &Self::hi(&self.header_style_link), &filepath);
+        Ok(())
+    }
+
+    /// Writes the file of a non table output format
+    #[allow(clippy::result_large_err)]
+    fn export(&self, write: impl FnOnce(&mut BufWriter<File>) -> dbeer::Result) -> dbeer::Result {
+        let filepath = self.create_export_file_format(self.output_format.extension());
+        dbeer_debug!(
+            "File path: {filepath}. Exporting as {:?}",
+            self.output_format
+        );
+
+        let mut writer = BufWriter::new(File::create(&filepath).map_err(dbeer::Error::Io)?);
+        write(&mut writer)?;
+        writer.flush().map_err(dbeer::Error::Io)?;
+
+        response::file(
+            Kind::Export,
+            &format!("setlocal filetype={}", self.output_format.filetype()),
+            &filepath,
+        );
+        Ok(())
+    }
+
     #[allow(clippy::result_large_err)]
     pub fn create_execute_result_file(&self, format: Format) -> dbeer::Result {
-        let (filepath, results) = match format {
-            Format::Standard(results) => (self.create_dbeer_file_format(), results),
-            Format::Json(results) => (self.create_dbeer_mongo_file_format(), results),
+        let (kind, filepath, results) = match format {
+            Format::Standard(results) => (Kind::Script, self.create_dbeer_file_format(), results),
+            Format::Json(results) => (
+                Kind::Documents,
+                self.create_dbeer_mongo_file_format(),
+                results,
+            ),
         };
-        println!("syn match dbeerStmtErr ' ' | hi link dbeerStmtErr ErrorMsg");
-        println!("{filepath}");
+        if kind == Kind::Documents {
+            response::row_count(results.len());
+        }
+        response::file(
+            kind,
+            "syn match dbeerStmtErr ' ' | hi link dbeerStmtErr ErrorMsg",
+            &filepath,
+        );
 
         dbeer_debug!("File path: {filepath}. Results {results:#?}");
 
         self.write_to_file(&filepath, &results)
     }
 
+    /// Writes every section to a single file, tables separated by a blank line
+    #[allow(clippy::result_large_err)]
+    pub fn create_sections_file(&self, sections: &[Section]) -> dbeer::Result {
+        let filepath = self.create_dbeer_file_format();
+        let mut cells_file = CellsFile::new(&filepath);
+        let mut lines = Vec::new();
+        let mut layouts = Vec::new();
+
+        for section in sections {
+            match section {
+                Section::Rows {
+                    title,
+                    result_set,
+                    footer,
+                } => {
+                    if lines.last().is_some_and(|line: &String| !line.is_empty()) {
+                        lines.push(String::new());
+                    }
+                    lines.push(title.clone());
+                    if result_set.is_empty() {
+                        lines.push(
+                            footer
+                                .clone()
+                                .unwrap_or("  Query has returned 0 results.".to_string()),
+                        );
+                    } else {
+                        // Rows keep their number in the whole result
+                        let offset = self.max_rows * self.page.saturating_sub(1);
+                        let (headers, rows) =
+                            result_set.headers_and_rows(&self.cell_format, offset);
+                        // Every row takes two lines below the three of the header
+                        let first_line = lines.len() + 4;
+                        for (i, values) in result_set.values.iter().enumerate() {
+                            for (column, value) in values.iter().enumerate() {
+                                let kind = result_set.kind(column);
+                                let cell = self.cell_format.cell(value.as_deref(), kind);
+                                cells_file.add(
+                                    first_line + 2 * i,
+                                    column + 1,
+                                    kind,
+                                    value,
+                                    &cell,
+                                )?;
+                            }
+                        }
+                        layouts.push(self.table_layout(
+                            &headers,
+                            lines.len() + 2,
+                            rows.len(),
+                            offset + 1,
+                        ));
+                        lines.extend(self.render(&headers, &rows));
+                        lines.extend(footer.clone());
+                    }
+                    lines.push(String::new());
+                }
+                Section::Status(status) => lines.push(status.clone()),
+            }
+        }
+        if lines.last().is_some_and(String::is_empty) {
+            lines.pop();
+        }
+
+        cells_file.finish()?;
+        Self::write_layout(&filepath, layouts)?;
+        dbeer_debug!("File path: {filepath}. Sections {lines:#?}");
+
+        response::file(
+            Kind::Script,
+            &format!(
+                "syn match dbeerStmtErr ' ' | hi link dbeerStmtErr ErrorMsg | {}",
+                Self::hi(&self.header_style_link)
+            ),
+            &filepath,
+        );
+
+        self.write_to_file(&filepath, &lines)
+    }
+
+    /// Writes rows already rendered. They are never cut, so there is no cells file
     #[allow(clippy::result_large_err)]
     pub fn generate(&self) -> dbeer::Result {
+        let table = self.render(&self.headers, &self.rows);
+        let filepath = self.create_dbeer_file_format();
+
+        dbeer_debug!("File path: {}", filepath);
+        dbeer_debug!("Table: {:#?}", table);
+
+        Self::write_layout(
+            &filepath,
+            vec![self.table_layout(&self.headers, 2, self.rows.len(), 1)],
+        )?;
+
+        response::row_count(self.rows.len());
+        response::file(Kind::Table, &Self::hi(&self.header_style_link), &filepath);
+
+        self.write_to_file(&filepath, &table)
+    }
+
+    /// Layout of a table whose header line is `header_line`, see `LAYOUT_EXTENSION`
+    fn table_layout(
+        &self,
+        headers: &HashMap<usize, Header>,
+        header_line: usize,
+        count: usize,
+        first_number: usize,
+    ) -> serde_json::Value {
+        let vertical = self.border_style.get().vertical;
+        let mut byte = vertical.len();
+        let mut display = display_width(vertical);
+        let mut columns = Vec::with_capacity(headers.len());
+
+        for key in 1..=headers.len() {
+            let header = &headers[&key];
+            let cell = Self::align(header, &header.name);
+            // The row counter is not a column of the statement
+            if key > 1 {
+                let name = header.name.trim();
+                let name_start = byte + cell.find(name).unwrap_or_default();
+                columns.push(serde_json::json!({
+                    "column": key - 1,
+                    "name": name,
+                    "name_start": name_start,
+                    "name_end": name_start + name.len(),
+                    "start": display,
+                    "end": display + header.length,
+                }));
+            }
+            byte += cell.len() + vertical.len();
+            display += header.length + display_width(vertical);
+        }
+
+        serde_json::json!({
+            "header_line": header_line,
+            "columns": columns,
+            "rows": {
+                "first_line": header_line + 2,
+                "step": 2,
+                "count": count,
+                "first_number": first_number,
+            },
+        })
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn write_layout(filepath: &str, tables: Vec<serde_json::Value>) -> dbeer::Result {
+        let path = format!("{filepath}.{}", Self::LAYOUT_EXTENSION);
+        let file = File::create(path).map_err(dbeer::Error::Io)?;
+        let layout = serde_json::json!({ "tables": tables });
+        response::layout(&layout);
+        serde_json::to_writer(BufWriter::new(file), &layout).map_err(dbeer::Error::Serde)
+    }
+
+    fn render(&self, headers: &HashMap<usize, Header>, rows: &[Vec<String>]) -> Vec<String> {
+        let mut table = self.header_lines(headers);
+        for (i, row) in rows.iter().enumerate() {
+            table.extend(self.row_lines(headers, row, i + 1 == rows.len()));
+        }
+        table
+    }
+
+    fn header_lines(&self, headers: &HashMap<usize, Header>) -> Vec<String> {
         let border = self.border_style.get();
 
-        // header
         let mut header_up = String::from(border.corner_up_left);
         let mut header_mid = String::from(border.vertical);
         let mut header_bottom = String::from(border.vertical_left);
 
-        let headers_len = self.headers.len();
+        let headers_len = headers.len();
         for key in 1..headers_len + 1 {
-            let length = self.headers.get(&key).unwrap().length;
+            let header = headers.get(&key).unwrap();
+            let length = header.length;
             header_up.push_str(&border.horizontal.repeat(length));
             header_bottom.push_str(&border.horizontal.repeat(length));
-            header_mid.push_str(&Self::add_spaces(
-                &self.headers.get(&key).unwrap().name,
-                length,
-            ));
+            header_mid.push_str(&Self::align(header, &header.name));
             header_mid.push_str(border.vertical);
 
             if key < headers_len {
@@ -110,54 +811,60 @@ impl Table {
             }
         }
 
-        // results
-        let mut table: Vec<String> = vec![header_up, header_mid, header_bottom];
-        let rows_len = self.rows.len() - 1;
-        let row_fields_len = self.rows.first().unwrap().len() - 1;
+        vec![header_up, header_mid, header_bottom]
+    }
 
-        for (i, row) in self.rows.iter().enumerate() {
-            let mut value = String::from(border.vertical);
-            let mut line = String::new();
+    /// Values of a row and the line below them, which closes the table after the last row
+    fn row_lines(
+        &self,
+        headers: &HashMap<usize, Header>,
+        row: &[String],
+        last: bool,
+    ) -> [String; 2] {
+        let border = self.border_style.get();
+        let mut value = String::from(border.vertical);
+        let mut line = String::new();
 
-            line.push_str(if i < rows_len {
-                border.vertical_left
-            } else {
-                border.corner_bottom_left
-            });
+        line.push_str(if last {
+            border.corner_bottom_left
+        } else {
+            border.vertical_left
+        });
 
-            for (j, field) in row.iter().enumerate() {
-                let length = self.headers.get(&(j + 1)).unwrap().length;
+        for (j, field) in row.iter().enumerate() {
+            let header = headers.get(&(j + 1)).unwrap();
+            let length = header.length;
 
-                value.push_str(&Self::add_spaces(field, length));
-                value.push_str(border.vertical);
+            value.push_str(&Self::align(header, field));
+            value.push_str(border.vertical);
 
-                line.push_str(&border.horizontal.repeat(length));
+            line.push_str(&border.horizontal.repeat(length));
 
-                line.push_str(match (i < rows_len, j < row_fields_len) {
-                    (true, true) => border.intersection,
-                    (true, false) => border.vertical_right,
-                    (false, true) => border.division_bottom,
-                    (false, false) => border.corner_bottom_right,
-                });
-            }
-            table.push(value);
-            table.push(line);
+            line.push_str(match (!last, j + 1 < row.len()) {
+                (true, true) => border.intersection,
+                (true, false) => border.vertical_right,
+                (false, true) => border.division_bottom,
+                (false, false) => border.corner_bottom_right,
+            });
         }
 
-        let filepath = self.create_dbeer_file_format();
-
-        dbeer_debug!("File path: {}", filepath);
-        dbeer_debug!("Table: {:#?}", table);
-
-        println!("{}", Self::hi(&self.headers, &self.header_style_link));
-        println!("{filepath}");
+        [value, line]
+    }
 
-        self.write_to_file(&filepath, &table)
+    /// Numeric columns are right aligned, keeping the space of the left side on the right
+    fn align(header: &Header, field: &str) -> String {
+        if header.kind.is_numeric() {
+            let field = format!("{} ", field.trim_start());
+            let padding = header.length.saturating_sub(display_width(&field) + 1);
+            format!(" {}{field}", " ".repeat(padding))
+        } else {
+            Self::add_spaces(field, header.length)
+        }
     }
 
     fn add_spaces(input_string: &str, len: usize) -> String {
         let mut result = String::from(input_string);
-        let input_str_len = input_string.chars().count();
+        let input_str_len = display_width(input_string);
 
         if len > input_str_len {
             let diff = len - input_str_len;
@@ -182,27 +889,18 @@ impl Table {
         writer.flush().map_err(dbeer::Error::Io)
     }
 
-    fn hi(headers: &HashMap<usize, Header>, style: &str) -> String {
-        let matches = headers
-            .iter()
-            .map(|(k, v)| {
-                format!(
-                    "syn match header{} '\\<{}\\>' | hi link header{} {} |",
-                    k,
-                    v.name.trim(),
-                    k,
-                    style
-                )
-            })
-            .collect::<Vec<_>>()
-            .join(" ");
-
-        dbeer_debug!("Highlight matches: {}", matches);
-        matches
+    /// Highlight group of the header names. The header cells are taken from the layout file
+    fn hi(style: &str) -> String {
+        format!("hi link dbeerHeader {style}")
+    }
+
+    /// Milliseconds keep apart the files of requests made within a second by `serve`
+    fn timestamp() -> String {
+        Local::now().format("%Y%m%d-%H%M%S%3f").to_string()
     }
 
     pub fn create_dbeer_file_format(&self) -> String {
-        let timestamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
+        let timestamp = Self::timestamp();
         format!(
             "{}/{}.{}",
             self.dest_folder,
@@ -212,13 +910,180 @@ impl Table {
     }
 
     pub fn create_dbeer_mongo_file_format(&self) -> String {
-        let timestamp = Local::now().format("%Y%m%d-%H%M%S").to_string();
+        self.create_export_file_format("json")
+    }
+
+    pub fn create_export_file_format(&self, extension: &str) -> String {
+        let timestamp = Self::timestamp();
         format!(
             "{}/{}.{}.{}",
             self.dest_folder,
             timestamp,
             Self::DBEER_EXTENSION,
-            "json"
+            extension
         )
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{CellFormat, ColumnKind, Header, Page, ResultSet, Section, Table, TableRows};
+    use crate::dbeer::Rows;
+
+    #[test]
+    fn test_result_set_sizes_columns() {
+        let result_set = ResultSet {
+            names: vec!["id".to_string(), "name".to_string()],
+            values: vec![
+                vec![Some("1".to_string()), Some("ñandú del sur".to_string())],
+                vec![Some("10".to_string()), None],
+                vec![Some("11".to_string()), Some("a\nb".to_string())],
+            ],
+            ..ResultSet::default()
+        };
+
+        let (headers, rows) = result_set.headers_and_rows(&CellFormat::default(), 0);
+        assert_eq!(rows[1], vec![" #2", " 10", " NULL"]);
+        assert_eq!(rows[2], vec![" #3", " 11", " a..."]);
+        assert_eq!(headers[&1].length, 4);
+        assert_eq!(headers[&2].length, 4);
+        assert_eq!(headers[&3].length, 15);
+    }
+
+    #[test]
+    fn test_page_keeps_rows_of_page() {
+        let mut result_set = ResultSet::default();
+        let mut page = Page::new(&mut result_set, 2, 2);
+        page.columns(&["id".to_string()]).unwrap();
+        for id in 1..=10 {
+            if page.done() {
+                break;
+            }
+            page.row(vec![Some(id.to_string())]).unwrap();
+        }
+
+        assert_eq!(
+            page.footer(),
+            Some("  Rows 3-4 (page 2). More rows available, next page: 3".to_string())
+        );
+        assert_eq!(
+            result_set.values,
+            vec![vec![Some("3".to_string())], vec![Some("4".to_string())]]
+        );
+    }
+
+    #[test]
+    fn test_numeric_columns_right_aligned() {
+        let result_set = ResultSet {
+            names: vec!["total".to_string(), "paid".to_string()],
+            kinds: vec![ColumnKind::Decimal, ColumnKind::Boolean],
+            values: vec![
+                vec![Some("1234.5".to_string()), Some("t".to_string())],
+                vec![Some("7".to_string()), None],
+            ],
+        };
+        let mut format = CellFormat::default();
+        format.numbers.thousands_separator = Some(',');
+        format.numbers.precision = Some(2);
+
+        let (headers, rows) = result_set.headers_and_rows(&format, 0);
+        let lines = Table::default().render(&headers, &rows);
+        assert_eq!(lines[1], "┃    ┃    TOTAL ┃ PAID ┃");
+        assert_eq!(lines[3], "┃ #1 ┃ 1,234.50 ┃ true ┃");
+        assert_eq!(lines[5], "┃ #2 ┃     7.00 ┃ NULL ┃");
+    }
+
+    #[test]
+    fn test_table_layout() {
+        let result_set = ResultSet {
+            names: vec!["id".to_string(), "名前".to_string()],
+            values: vec![vec![Some("1".to_string()), Some("a".to_string())]],
+            ..ResultSet::default()
+        };
+
+        let (headers, rows) = result_set.headers_and_rows(&CellFormat::default(), 0);
+        let table = Table::default();
+        let lines = table.render(&headers, &rows);
+        let layout = table.table_layout(&headers, 2, rows.len(), 1);
+
+        let name = &layout["columns"][1];
+        let (start, end) = (
+            name["name_start"].as_u64().unwrap() as usize,
+            name["name_end"].as_u64().unwrap() as usize,
+        );
+        assert_eq!(&lines[1][start..end], "名前");
+        assert_eq!(name["column"], 2);
+        assert_eq!(
+            (name["start"].as_u64(), name["end"].as_u64()),
+            (Some(11), Some(17))
+        );
+        assert_eq!(layout["rows"]["first_line"], 4);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_spill_file() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let first = TableRows::new(CellFormat::default()).unwrap();
+        let second = TableRows::new(CellFormat::default()).unwrap();
+        assert_ne!(first.spill_path, second.spill_path);
+        let mode = std::fs::metadata(&first.spill_path)
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let path = first.spill_path.clone();
+        drop(first);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_header_width() {
+        assert_eq!(Header::new("straße").length, 9);
+        assert_eq!(Header::new("straße").name, " STRASSE");
+    }
+
+    #[test]
+    fn test_sections_layout_of_page() {
+        let dest_folder =
+            std::env::temp_dir().join(format!("dbeer_sections_{}", std::process::id()));
+        std::fs::create_dir_all(&dest_folder).unwrap();
+        let table = Table {
+            dest_folder: dest_folder.to_string_lossy().to_string(),
+            max_rows: 2,
+            page: 2,
+            ..Table::default()
+        };
+        let result_set = ResultSet {
+            names: vec!["id".to_string()],
+            values: vec![vec![Some("3".to_string())], vec![Some("4".to_string())]],
+            ..ResultSet::default()
+        };
+        table
+            .create_sections_file(&[Section::Rows {
+                title: "1) select id".to_string(),
+                result_set,
+                footer: None,
+            }])
+            .unwrap();
+
+        let file = std::fs::read_dir(&dest_folder)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .find(|path| path.extension().is_some_and(|e| e == "dbeer"))
+            .unwrap();
+        let lines = std::fs::read_to_string(&file).unwrap();
+        let layout: serde_json::Value = serde_json::from_str(
+            &std::fs::read_to_string(file.with_extension("dbeer.layout")).unwrap(),
+        )
+        .unwrap();
+        std::fs::remove_dir_all(&dest_folder).unwrap();
+
+        let rows = &layout["tables"][0]["rows"];
+        assert_eq!(rows["first_number"], 3);
+        let first_line = rows["first_line"].as_u64().unwrap() as usize;
+        assert!(lines.lines().nth(first_line - 1).unwrap().contains("#3"));
+    }
+}
@@ -1,9 +1,16 @@
 use std::env;
+use std::path::Path;
+use std::time::{Duration, Instant};
 
 use crate::dbeer::{
+    cancel::{cancel_on_signals, exit_if_signaled},
     command::Command,
     dispatch::process,
+    history::{self, Entry},
     logger::{debug, error, logger_init},
+    profile,
+    response::{Protocol, finish, message, response_init},
+    serve::serve,
 };
 mod dbeer;
 
@@ -13,33 +20,87 @@ fn main() {
     let mut command = Command::new();
 
     let args: Vec<String> = env::args().collect();
-    let mut i = 1;
+    let serve_mode = args.get(1).is_some_and(|arg| arg == "serve");
+    // The first wrong argument is reported once the protocol is known
+    let mut argument_error = None;
+    let mut i = if serve_mode { 2 } else { 1 };
     while i < args.len() {
-        match args[i].as_str() {
-            "-engine" => command.engine = args[i + 1].clone(),
-            "-conn-str" => command.conn_str = args[i + 1].clone(),
-            "-dbname" => command.db_name = args[i + 1].clone(),
-            "-queries" => command.queries = args[i + 1].clone(),
-            "-border-style" => command.border_style = args[i + 1].clone().into(),
-            "-dest-folder" => command.dest_folder = args[i + 1].clone(),
-            "-dbeer-log-file" => dbeer_log_file = args[i + 1].clone(),
-            "-option" => command.action = args[i + 1].clone().into(), // action in Lua
-            "-header-style-link" => command.header_style_link = args[i + 1].clone(),
-            "-log-debug" => log_debug = args[i + 1].clone().parse().unwrap_or(log_debug),
-            _ => break,
+        let flag = args[i].as_str();
+        let Some(value) = args.get(i + 1).cloned() else {
+            argument_error.get_or_insert(format!("Argument {flag} has no value"));
+            break;
+        };
+        match flag {
+            "-dbeer-log-file" => dbeer_log_file = value,
+            "-log-debug" => log_debug = value.parse().unwrap_or(log_debug),
+            flag => {
+                if !command.set(flag, value) {
+                    argument_error.get_or_insert(format!("Argument {flag} is not valid"));
+                }
+            }
         }
         i += 2;
     }
 
     logger_init(&dbeer_log_file, log_debug).expect("Logger init failed!");
 
+    if command.history_file.is_empty() && !dbeer_log_file.is_empty() {
+        command.history_file = Path::new(&dbeer_log_file)
+            .with_file_name(history::FILE_NAME)
+            .to_string_lossy()
+            .to_string();
+    }
+
     dbeer_debug!("Debug enabled!");
-    dbeer_debug!("Parsed params: {command:#?}");
+    dbeer_debug!("Parsed params: {:#?}", command.redacted());
+
+    if let Err(e) = cancel_on_signals() {
+        dbeer_error!("[ERROR] Signals are not handled: {e}");
+    }
 
-    let engine_type = command.engine.clone();
-    if let Err(e) = process(command, engine_type.into()) {
-        let error_msg = format!("[ERROR] {e}");
-        println!("{error_msg}");
-        dbeer_error!("{error_msg}");
+    if serve_mode {
+        if let Some(e) = argument_error {
+            dbeer_error!("[ERROR] {e}");
+            response_init(Protocol::Json);
+            finish(&Err(dbeer::Error::Msg(e)), Duration::ZERO);
+            return;
+        }
+        if let Err(e) = serve(command) {
+            dbeer_error!("[ERROR] Serve stopped: {e}");
+        }
+        return;
+    }
+
+    let started = Instant::now();
+    let mut entry = None;
+    let result = match Protocol::parse(&command.protocol) {
+        Ok(protocol) => {
+            response_init(protocol);
+            let applied = match argument_error {
+                Some(e) => Err(dbeer::Error::Msg(e)),
+                None => profile::apply(&mut command),
+            };
+            match applied {
+                Ok(()) => {
+                    entry = Entry::start(&command);
+                    let engine_type = command.engine.clone();
+                    process(command, engine_type.into())
+                }
+                Err(e) => Err(e),
+            }
+        }
+        Err(e) => Err(e),
+    };
+
+    if let Err(e) = &result {
+        dbeer_error!("[ERROR] {e}");
+    }
+    let elapsed = started.elapsed();
+    finish(&result, elapsed);
+    if let Some(entry) = entry
+        && let Err(e) = entry.write(&result, elapsed)
+    {
+        dbeer_error!("[ERROR] History not written: {e}");
     }
+    exit_if_signaled();
 }